@@ -0,0 +1,976 @@
+mod serve;
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+
+use crate::{
+    loaders::{
+        self, fabric, forge, maven, minecraft, quilt, Install, InstallReport, JarCompression,
+        Loader, LoaderVersion, Reporter, Side,
+    },
+    lockfile::Lockfile,
+    presets::Presets,
+    settings::Settings,
+    utils::{self, checksums::Checksums, cleanup::Cleanup},
+    Args,
+};
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+    let args = match args.preset.clone() {
+        Some(name) => apply_preset(args, &name)?,
+        None => args,
+    };
+
+    let runtime = tokio::runtime::Runtime::new()?;
+
+    let result = runtime.block_on(async move {
+        if let Some(port) = args.serve {
+            serve::serve(port).await
+        } else if args.verify {
+            verify(args).await
+        } else if args.print_profile {
+            print_profile(args).await
+        } else if args.check {
+            check(args).await
+        } else if args.diff {
+            diff(args).await
+        } else if args.print_dirs {
+            print_dirs(args)
+        } else if args.clean {
+            clean(args).await
+        } else if let Some(output) = args.output_zip.clone() {
+            install_zip(args, output).await
+        } else {
+            install(args).await
+        }
+    });
+
+    // Exits the way shells expect of a program stopped with Ctrl-C
+    match result {
+        Err(err) if err.is::<Cancelled>() => {
+            eprintln!("{}", err);
+            std::process::exit(130);
+        }
+        result => result,
+    }
+}
+
+/// The install was stopped with Ctrl-C, whatever it had written is already removed.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Install cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Fills in whatever the command line left out from a saved preset.
+fn apply_preset(mut args: Args, name: &str) -> anyhow::Result<Args> {
+    let presets = Presets::try_load().context("Failed to load presets")?;
+    let preset = match presets.get(name) {
+        Some(preset) => preset,
+        None => anyhow::bail!(
+            "Unknown preset: {}, saved presets are: {}",
+            name,
+            presets.names().join(", ")
+        ),
+    };
+
+    args.loader = preset.loader;
+    args.side = preset.side;
+    if args.dir.is_empty() {
+        args.dir.extend(preset.dir.clone());
+    }
+    if args.minecraft.is_none() {
+        args.minecraft = preset.minecraft.clone();
+    }
+    if args.loader_version.is_none() && args.loader_build.is_none() {
+        args.loader_version = preset.loader_version.clone();
+    }
+
+    args.no_profile |= preset.no_profile;
+    args.no_placeholder_jar |= preset.no_placeholder_jar;
+    args.keep_existing |= preset.keep_existing;
+    args.download_server |= preset.download_server;
+    args.create_scripts |= preset.create_scripts;
+    args.accept_eula |= preset.accept_eula;
+    if preset.compress_jar {
+        args.jar_compression = JarCompression::Deflated;
+    }
+
+    Ok(args)
+}
+
+fn install_dirs(args: &Args) -> anyhow::Result<Vec<PathBuf>> {
+    if let Some(dir) = &args.docker_out {
+        if args.side != Side::Server {
+            anyhow::bail!("--docker-out only supports server installs");
+        }
+
+        return Ok(vec![dir.clone()]);
+    }
+    if !args.dir.is_empty() {
+        return Ok(args.dir.clone());
+    }
+
+    match args.side {
+        Side::Client => Ok(vec![utils::get_minecraft_directory()?]),
+        Side::Server => Ok(vec![std::env::current_dir()?]),
+    }
+}
+
+/// Prints where the installer reads and writes with the current arguments, as `key=value`
+/// lines or JSON.
+fn print_dirs(args: Args) -> anyhow::Result<()> {
+    let data_dir = utils::get_data_directory()?;
+    let dirs = [
+        ("minecraft_dir", utils::get_minecraft_directory()?),
+        ("data_dir", data_dir.clone()),
+        ("settings", Settings::path()?),
+        ("presets", Presets::path()?),
+        ("checksums", Checksums::path()?),
+        // Logs go to stdout, only crash reports are written to disk
+        ("crash_logs", data_dir),
+    ];
+    let install_dirs = install_dirs(&args)?;
+
+    if args.json {
+        let mut json = serde_json::Map::new();
+        json.insert(
+            "install_dirs".to_string(),
+            serde_json::to_value(&install_dirs)?,
+        );
+        for (key, path) in dirs {
+            json.insert(key.to_string(), serde_json::to_value(path)?);
+        }
+
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    } else {
+        for dir in &install_dirs {
+            println!("install_dir={}", dir.display());
+        }
+        for (key, path) in dirs {
+            println!("{}={}", key, path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Picks the requested version out of a list, or the default one if none was requested.
+fn select<T: Clone>(
+    kind: &str,
+    versions: &[T],
+    requested: Option<&str>,
+    version: impl Fn(&T) -> &str,
+    default: impl Fn(&T) -> bool,
+) -> anyhow::Result<T> {
+    let selected = match requested {
+        Some(requested) => versions.iter().find(|&v| version(v) == requested),
+        None => versions
+            .iter()
+            .find(|&v| default(v))
+            .or_else(|| versions.first()),
+    };
+
+    match (selected, requested) {
+        (Some(selected), _) => Ok(selected.clone()),
+        (None, Some(requested)) => {
+            let suggestions = suggest(requested, versions.iter().map(version));
+            if suggestions.is_empty() {
+                anyhow::bail!("Unknown {} version: {}", kind, requested)
+            }
+
+            anyhow::bail!(
+                "Unknown {} version: {}, did you mean: {}?",
+                kind,
+                requested,
+                suggestions.join(", ")
+            )
+        }
+        (None, None) => anyhow::bail!("No {} versions available", kind),
+    }
+}
+
+/// Versions close to a mistyped one, ones it's a prefix of first and then the fewest edits away.
+fn suggest<'a>(requested: &str, versions: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    const MAX_SUGGESTIONS: usize = 3;
+
+    let versions = versions.collect::<Vec<_>>();
+
+    let prefixed = versions
+        .iter()
+        .copied()
+        .filter(|version| version.starts_with(requested))
+        .take(MAX_SUGGESTIONS)
+        .collect::<Vec<_>>();
+    if !prefixed.is_empty() {
+        return prefixed;
+    }
+
+    // More edits than a third of the version is more likely a different version than a typo
+    let max_distance = (requested.len() / 3).max(1);
+    let mut close = versions
+        .into_iter()
+        .map(|version| (edit_distance(requested, version), version))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect::<Vec<_>>();
+    // Stable, so equally close versions keep the list's newest first order
+    close.sort_by_key(|(distance, _)| *distance);
+
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, version)| version)
+        .collect()
+}
+
+/// The Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a == *b {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Picks the version with the given build number, which unlike version strings only ever go up.
+fn select_build<T: Clone>(
+    kind: &str,
+    versions: &[T],
+    build: i64,
+    build_of: impl Fn(&T) -> i64,
+) -> anyhow::Result<T> {
+    let mut matching = versions.iter().filter(|&v| build_of(v) == build);
+
+    match (matching.next(), matching.next()) {
+        (Some(selected), None) => Ok(selected.clone()),
+        (Some(_), Some(_)) => anyhow::bail!("{} build {} matches several versions", kind, build),
+        (None, _) => anyhow::bail!("Unknown {} build: {}", kind, build),
+    }
+}
+
+/// Picks the requested Minecraft version from the loader's list, with
+/// `--allow-unsupported-minecraft` a version missing from it is taken as is.
+fn select_minecraft(
+    args: &Args,
+    versions: &[minecraft::Version],
+) -> anyhow::Result<minecraft::Version> {
+    let requested = args.minecraft.as_deref();
+
+    match requested {
+        Some(requested)
+            if args.allow_unsupported_minecraft
+                && !versions.iter().any(|v| v.version == requested) =>
+        {
+            eprintln!(
+                "Minecraft {} isn't listed by {:?}, trying it anyway",
+                requested, args.loader
+            );
+
+            Ok(minecraft::Version {
+                version: requested.to_string(),
+                stable: false,
+                kind: None,
+            })
+        }
+        _ => select(
+            "Minecraft",
+            versions,
+            requested,
+            |v| v.version.as_str(),
+            |v| v.stable,
+        ),
+    }
+}
+
+async fn resolve(args: &Args) -> anyhow::Result<(LoaderVersion, minecraft::Version)> {
+    let urls = args.base_urls();
+    let requested_minecraft = args.minecraft.as_deref();
+    let requested_version = args.loader_version.as_deref();
+
+    match args.loader {
+        Loader::Fabric => {
+            let minecraft = select_minecraft(
+                args,
+                &fabric::Commands::fetch_minecraft(urls.clone()).await?,
+            )?;
+            let versions = fabric::Commands::fetch_versions(urls.clone()).await?;
+            let version = match args.loader_build {
+                Some(build) => select_build("Fabric", &versions, build, |v| v.build)?,
+                None => select(
+                    "Fabric",
+                    &versions,
+                    requested_version,
+                    |v| v.version.as_str(),
+                    |v| v.recommended,
+                )?,
+            };
+
+            Ok((LoaderVersion::Fabric(version), minecraft))
+        }
+        Loader::Forge => {
+            if args.loader_build.is_some() {
+                anyhow::bail!("Forge has no build numbers, use --loader-version instead");
+            }
+            // Forge builds are made for one Minecraft version, there's no profile to try
+            if args.allow_unsupported_minecraft {
+                anyhow::bail!("--allow-unsupported-minecraft isn't supported for Forge");
+            }
+
+            let versions = forge::Commands::fetch_versions(urls.clone()).await?;
+
+            let minecraft = select(
+                "Minecraft",
+                &forge::minecraft_versions(&versions),
+                requested_minecraft,
+                |v| v.version.as_str(),
+                |v| v.stable,
+            )?;
+            let versions = versions
+                .into_iter()
+                .filter(|v| v.minecraft == minecraft.version)
+                .collect::<Vec<_>>();
+            let version = select(
+                "Forge",
+                &versions,
+                requested_version,
+                |v| v.version.as_str(),
+                |v| v.recommended,
+            )?;
+
+            Ok((LoaderVersion::Forge(version), minecraft))
+        }
+        Loader::Quilt => {
+            let minecraft =
+                select_minecraft(args, &quilt::Commands::fetch_minecraft(urls.clone()).await?)?;
+            let versions = quilt::Commands::fetch_versions(urls).await?;
+            let version = match args.loader_build {
+                Some(build) => select_build("Quilt", &versions, build, |v| i64::from(v.build))?,
+                None => select(
+                    "Quilt",
+                    &versions,
+                    requested_version,
+                    |v| v.version.as_str(),
+                    |v| v.recommended,
+                )?,
+            };
+
+            Ok((LoaderVersion::Quilt(version), minecraft))
+        }
+    }
+}
+
+async fn print_profile(args: Args) -> anyhow::Result<()> {
+    let (version, minecraft) = resolve(&args).await?;
+
+    let profile =
+        loaders::fetch_profile(&args.base_urls(), &version, args.side, &minecraft).await?;
+
+    println!("{}", args.json_format.serialize(&profile)?);
+
+    Ok(())
+}
+
+/// Prints one line of the `--check` report, returning whether the check passed.
+fn report_check(name: &str, result: anyhow::Result<String>) -> bool {
+    match result {
+        Ok(detail) => {
+            println!("ok    {}: {}", name, detail);
+            true
+        }
+        Err(err) => {
+            println!("FAIL  {}: {:#}", name, err);
+            false
+        }
+    }
+}
+
+async fn check(args: Args) -> anyhow::Result<()> {
+    let mut passed = true;
+
+    // Only a probe file is written to see if the directory is writable, and removed right after
+    for dir in install_dirs(&args)? {
+        let result = utils::check_install_location(args.side, &dir).await;
+        passed &= report_check("directory", result.map(|()| dir.display().to_string()));
+    }
+
+    match resolve(&args).await {
+        Ok((version, minecraft)) => {
+            passed &= report_check(
+                "versions",
+                Ok(format!(
+                    "{} {} for Minecraft {}",
+                    version.name(),
+                    version,
+                    minecraft
+                )),
+            );
+
+            let result = match version {
+                LoaderVersion::Quilt(_) => {
+                    loaders::fetch_profile(&args.base_urls(), &version, args.side, &minecraft)
+                        .await
+                        .map(|_| "downloaded".to_string())
+                }
+                LoaderVersion::Fabric(_) | LoaderVersion::Forge(_) => Ok(format!(
+                    "skipped, not supported for {:?} yet",
+                    version.loader()
+                )),
+            };
+            passed &= report_check("profile", result);
+        }
+        Err(err) => passed &= report_check("versions", Err(err)),
+    }
+
+    if !passed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+async fn install_into(
+    args: &Args,
+    dir: &Path,
+    resolved: Option<&(LoaderVersion, minecraft::Version)>,
+    library_cache: Option<PathBuf>,
+    progress: Reporter,
+) -> anyhow::Result<InstallReport> {
+    let (side, version, minecraft, locked) = match resolved {
+        Some((version, minecraft)) => (args.side, version.clone(), minecraft.clone(), None),
+        None => {
+            let lock = Lockfile::read(dir).await?;
+
+            (
+                lock.side,
+                lock.version.clone(),
+                lock.minecraft.clone(),
+                Some(lock),
+            )
+        }
+    };
+
+    let urls = args.base_urls();
+
+    let mut mirrors = maven::Mirrors::new(&urls);
+    for mirror in &args.maven_mirror {
+        mirrors.add_arg(mirror);
+    }
+
+    // Credentials only ever go to the mirrors they were given for, never the public repositories
+    if let Some(username) = &args.maven_user {
+        let credentials = maven::Credentials {
+            username: username.clone(),
+            password: args.maven_pass.clone().unwrap_or_default(),
+        };
+
+        for mirror in &args.maven_mirror {
+            let (_, url) = maven::split_arg(mirror);
+            let host = reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .with_context(|| format!("Maven mirror has no host: {}", url))?;
+
+            mirrors.add_credentials(&host, credentials.clone());
+        }
+    }
+    if let Some(path) = &args.maven_netrc {
+        let contents = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read netrc file: {}", path.display()))?;
+
+        mirrors.add_netrc(&contents)?;
+    }
+
+    if side == Side::Client && !args.allow_downgrade {
+        let profiles = loaders::existing_profiles(dir.to_path_buf()).await?;
+
+        if let Some(newer) = loaders::newer_installed(&profiles, &version, &minecraft.version) {
+            anyhow::bail!(
+                "This would downgrade {} from {} to {}, pass --allow-downgrade to do it anyway",
+                dir.display(),
+                newer.version,
+                version
+            );
+        }
+    }
+
+    println!(
+        "Installing {} {} for Minecraft {} into {}",
+        version.name(),
+        version,
+        minecraft,
+        dir.display()
+    );
+
+    // Later directories copy from the first one, so only it actually downloads anything
+    if library_cache.is_none() {
+        let estimate = loaders::estimate_download(
+            urls.clone(),
+            mirrors.clone(),
+            version.clone(),
+            side,
+            dir.to_path_buf(),
+            minecraft.clone(),
+        )
+        .await;
+
+        match estimate {
+            Ok(Some(estimate)) => {
+                utils::check_disk_space(dir, estimate.bytes)?;
+
+                println!("Downloading {} of libraries", estimate);
+            }
+            Ok(None) => {}
+            Err(err) => tracing::warn!(error = ?err, "Failed to estimate download size"),
+        }
+    }
+
+    let cleanup = Cleanup::default();
+    let install = Install::builder()
+        .version(version)
+        .side(side)
+        .dir(dir)
+        .minecraft(minecraft)
+        .generate_profile(!args.no_profile)
+        .placeholder_jar(!args.no_placeholder_jar)
+        .vanilla_jar(args.vanilla_jar)
+        .keep_existing(args.keep_existing)
+        .json_format(args.json_format)
+        .generate_script(args.create_scripts)
+        .docker(args.docker_out.is_some())
+        .smoke_test(args.smoke_test)
+        .java(args.java.clone())
+        // Zipped installs are unpacked on another host, with its own Java
+        .portable(args.output_zip.is_some())
+        .keep_hashed_libraries(args.keep_hashed_libraries)
+        .jar_compression(args.jar_compression)
+        .download_jar(args.download_server)
+        .accept_eula(args.accept_eula)
+        .icon(args.icon.clone())
+        .jvm_args(args.jvm_args.clone())
+        .replace_profile(args.force)
+        .library_cache(library_cache)
+        .locked(locked)
+        .extra_libs(args.extra_lib.clone())
+        .max_bandwidth(args.max_bandwidth)
+        .preflight(!args.skip_preflight)
+        .mirrors(mirrors)
+        .urls(urls)
+        .progress(progress)
+        .cleanup(cleanup.clone())
+        .build()?;
+
+    let installing = async {
+        match args.timeout_total {
+            Some(secs) => {
+                match tokio::time::timeout(Duration::from_secs(secs), loaders::install(install))
+                    .await
+                {
+                    Ok(report) => report,
+                    Err(_) => {
+                        cleanup.remove_all();
+                        Err(anyhow::anyhow!("Install exceeded {} seconds", secs))
+                    }
+                }
+            }
+            None => loaders::install(install).await,
+        }
+    };
+
+    // Dropping the install future stops it at its next await, whatever it was still writing is
+    // tracked by the cleanup
+    let report = tokio::select! {
+        report = installing => report?,
+        _ = tokio::signal::ctrl_c() => {
+            cleanup.remove_all();
+            return Err(Cancelled.into());
+        }
+    };
+
+    match &report.profile_name {
+        Some(profile) if report.up_to_date => {
+            println!("Launcher version {} is already up to date", profile)
+        }
+        Some(profile) => println!("Created launcher version {}", profile),
+        None => {}
+    }
+    if let Some(jar) = &report.launch_jar {
+        println!("Start the server with {}", jar.display());
+    }
+    if !report.libraries.is_empty() {
+        println!("Installed {} libraries", report.libraries.len());
+    }
+    for warning in &report.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    println!("Finished in {:.1}s", report.elapsed.as_secs_f64());
+
+    Ok(report)
+}
+
+/// The `libraries` directory of `--seed-from`, to take libraries from before downloading them.
+fn seed_cache(args: &Args) -> anyhow::Result<Option<PathBuf>> {
+    let dir = match &args.seed_from {
+        Some(dir) => dir,
+        None => return Ok(None),
+    };
+
+    let libraries = dir.join("libraries");
+    if !libraries.is_dir() {
+        anyhow::bail!("{} has no libraries directory to seed from", dir.display());
+    }
+
+    Ok(Some(libraries))
+}
+
+async fn install(args: Args) -> anyhow::Result<()> {
+    let dirs = install_dirs(&args)?;
+
+    // Locked installs take their versions from each directory's lockfile instead
+    let resolved = if args.locked {
+        None
+    } else {
+        Some(resolve(&args).await?)
+    };
+
+    // Libraries are only downloaded once, later installs copy them from the first one
+    let mut library_cache = seed_cache(&args)?;
+    let mut cached_install = false;
+    let mut failures = Vec::new();
+
+    for dir in &dirs {
+        let result = install_into(
+            &args,
+            dir,
+            resolved.as_ref(),
+            library_cache.clone(),
+            Reporter::default(),
+        )
+        .await;
+
+        match result {
+            Ok(_) => {
+                // The first install has everything, unlike a seed that may be missing some
+                if !cached_install {
+                    library_cache = Some(dir.join("libraries"));
+                    cached_install = true;
+                }
+            }
+            // Cancelling stops the remaining directories too
+            Err(err) if err.is::<Cancelled>() => return Err(err),
+            Err(err) => {
+                eprintln!("Failed to install into {}: {:#}", dir.display(), err);
+                failures.push(dir);
+            }
+        }
+    }
+
+    if dirs.len() > 1 {
+        println!(
+            "Installed into {} of {} directories",
+            dirs.len() - failures.len(),
+            dirs.len()
+        );
+
+        for dir in &failures {
+            println!("  failed: {}", dir.display());
+        }
+    }
+
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Installs a server into a temporary directory and packs it into a zip that can be unpacked and
+/// run on a host as is.
+async fn install_zip(args: Args, output: PathBuf) -> anyhow::Result<()> {
+    if args.side != Side::Server {
+        anyhow::bail!("--output-zip only supports server installs");
+    }
+
+    let resolved = resolve(&args).await?;
+
+    let dir = std::env::temp_dir().join(format!("anymc-installer-{}", std::process::id()));
+
+    let result = async {
+        install_into(
+            &args,
+            &dir,
+            Some(&resolved),
+            seed_cache(&args)?,
+            Reporter::default(),
+        )
+        .await?;
+        utils::zip_directory(dir.clone(), output.clone()).await
+    }
+    .await;
+
+    if let Err(err) = tokio::fs::remove_dir_all(&dir).await {
+        tracing::warn!(error = ?err, "Failed to remove temporary install directory");
+    }
+
+    result?;
+
+    println!("Packed the server install into {}", output.display());
+
+    Ok(())
+}
+
+async fn clean(args: Args) -> anyhow::Result<()> {
+    let dirs = install_dirs(&args)?;
+    let (version, minecraft) = resolve(&args).await?;
+    let urls = args.base_urls();
+
+    for dir in &dirs {
+        let stale = loaders::stale_libraries(&urls, &version, dir, &minecraft).await?;
+
+        if stale.is_empty() {
+            println!("No stale libraries in {}", dir.display());
+
+            continue;
+        }
+
+        for path in &stale {
+            if args.force {
+                tokio::fs::remove_file(path).await?;
+                println!("Removed {}", path.display());
+            } else {
+                println!("Would remove {}", path.display());
+            }
+        }
+
+        if !args.force {
+            println!(
+                "Run again with --force to remove {} stale file(s) from {}",
+                stale.len(),
+                dir.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// How installing a version would change the libraries of an existing server install.
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+struct InstallDiff {
+    dir: PathBuf,
+    installed: String,
+    fresh: String,
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<ChangedLibrary>,
+}
+
+/// A library in both installs, told apart from an added one by its group, name and classifier.
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+struct ChangedLibrary {
+    name: String,
+    from: String,
+    to: String,
+}
+
+impl InstallDiff {
+    fn new(dir: &Path, lock: &Lockfile, fresh: (&LoaderVersion, &minecraft::Version)) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            installed: describe_version(&lock.version, &lock.minecraft),
+            fresh: describe_version(fresh.0, fresh.1),
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    /// Sorts the libraries of both sides into added, removed and changed ones.
+    fn compare<'a>(
+        &mut self,
+        installed: impl Iterator<Item = &'a str>,
+        fresh: impl Iterator<Item = &'a str>,
+    ) -> anyhow::Result<()> {
+        let installed = by_artifact(installed)?;
+        let fresh = by_artifact(fresh)?;
+
+        for (key, (name, version)) in &fresh {
+            match installed.get(key) {
+                None => self.added.push(name.to_string()),
+                Some((_, old)) if old != version => self.changed.push(ChangedLibrary {
+                    name: key.clone(),
+                    from: old.clone(),
+                    to: version.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        self.removed = installed
+            .iter()
+            .filter(|(key, _)| !fresh.contains_key(*key))
+            .map(|(_, (name, _))| name.to_string())
+            .collect();
+
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Maps libraries by what identifies them across versions, group, name and classifier.
+fn by_artifact<'a>(
+    names: impl Iterator<Item = &'a str>,
+) -> anyhow::Result<BTreeMap<String, (&'a str, String)>> {
+    names
+        .map(|name| {
+            let coord = name.parse::<maven::MavenCoord>()?;
+            let key = match &coord.classifier {
+                Some(classifier) => format!("{}:{}:{}", coord.group, coord.name, classifier),
+                None => format!("{}:{}", coord.group, coord.name),
+            };
+
+            anyhow::Ok((key, (name, coord.version)))
+        })
+        .collect()
+}
+
+fn describe_version(version: &LoaderVersion, minecraft: &minecraft::Version) -> String {
+    format!("{} {} for Minecraft {}", version.name(), version, minecraft)
+}
+
+async fn diff(mut args: Args) -> anyhow::Result<()> {
+    let dirs = install_dirs(&args)?;
+    let urls = args.base_urls();
+    let requested_minecraft = args.minecraft.clone();
+
+    let mut diffs = Vec::with_capacity(dirs.len());
+
+    for dir in &dirs {
+        let lock = Lockfile::read(dir)
+            .await
+            .context("Only server installs record what they installed to compare against")?;
+
+        // Compare against the same loader, and the installed Minecraft unless asked otherwise
+        args.loader = lock.version.loader();
+        args.side = lock.side;
+        args.minecraft = requested_minecraft
+            .clone()
+            .or_else(|| Some(lock.minecraft.to_string()));
+
+        let (version, minecraft) = resolve(&args).await?;
+        let profile = loaders::fetch_profile(&urls, &version, lock.side, &minecraft).await?;
+
+        let fresh = profile["libraries"]
+            .as_array()
+            .context("Profile has no libraries")?
+            .iter()
+            .filter_map(|lib| lib["name"].as_str());
+        let installed = lock.libraries.iter().map(|lib| lib.name.as_str());
+
+        let mut diff = InstallDiff::new(dir, &lock, (&version, &minecraft));
+        diff.compare(installed, fresh)?;
+
+        diffs.push(diff);
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&diffs)?);
+
+        return Ok(());
+    }
+
+    for diff in &diffs {
+        println!("{}", diff.dir.display());
+        println!("  installed: {}", diff.installed);
+        println!("  fresh:     {}", diff.fresh);
+
+        if diff.is_empty() {
+            println!("  No library changes");
+        }
+        for name in &diff.added {
+            println!("  + {}", name);
+        }
+        for name in &diff.removed {
+            println!("  - {}", name);
+        }
+        for changed in &diff.changed {
+            println!("  ~ {} {} -> {}", changed.name, changed.from, changed.to);
+        }
+    }
+
+    Ok(())
+}
+
+async fn verify(args: Args) -> anyhow::Result<()> {
+    let dirs = install_dirs(&args)?;
+
+    // Servers have a single launch jar, only client installs are found by their versions
+    let versions = match args.side {
+        Side::Client => {
+            let minecraft = args
+                .minecraft
+                .as_deref()
+                .context("--minecraft is required to verify a client install")?;
+            let version = args
+                .loader_version
+                .as_deref()
+                .context("--loader-version is required to verify a client install")?;
+
+            Some((minecraft, version))
+        }
+        Side::Server => None,
+    };
+
+    let mut failed = false;
+
+    for dir in &dirs {
+        let problems = loaders::verify(args.loader, args.side, dir, versions).await?;
+
+        if problems.is_empty() {
+            println!("Install in {} is intact", dir.display());
+
+            continue;
+        }
+
+        for problem in &problems {
+            eprintln!("{}", problem);
+        }
+        eprintln!("Found {} problem(s) in {}", problems.len(), dir.display());
+
+        failed = true;
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
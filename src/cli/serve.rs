@@ -0,0 +1,171 @@
+use std::{net::Ipv4Addr, path::PathBuf};
+
+use anyhow::Context;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{tcp::OwnedWriteHalf, TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+use crate::{
+    loaders::{InstallReport, Loader, Reporter, Side},
+    Args,
+};
+
+/// One install, sent as a single line of JSON.
+#[derive(Debug)]
+#[derive(serde::Deserialize)]
+struct Request {
+    #[serde(default)]
+    loader: Loader,
+    #[serde(default)]
+    side: Side,
+    dir: PathBuf,
+    minecraft: Option<String>,
+    loader_version: Option<String>,
+    #[serde(default = "enabled")]
+    generate_profile: bool,
+    #[serde(default = "enabled")]
+    placeholder_jar: bool,
+    #[serde(default)]
+    keep_existing: bool,
+    #[serde(default)]
+    download_server: bool,
+    #[serde(default)]
+    create_scripts: bool,
+    #[serde(default)]
+    accept_eula: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Request {
+    /// The command line arguments a headless install of the request would be given.
+    fn into_args(self) -> Args {
+        Args {
+            no_gui: true,
+            loader: self.loader,
+            side: self.side,
+            dir: vec![self.dir],
+            minecraft: self.minecraft,
+            loader_version: self.loader_version,
+            no_profile: !self.generate_profile,
+            no_placeholder_jar: !self.placeholder_jar,
+            keep_existing: self.keep_existing,
+            download_server: self.download_server,
+            create_scripts: self.create_scripts,
+            accept_eula: self.accept_eula,
+            ..Default::default()
+        }
+    }
+}
+
+/// Sent back as JSON lines, any number of progress events followed by one result.
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum Event {
+    Progress { step: String, fraction: Option<f32> },
+    Done { report: InstallReport },
+    Error { message: String },
+}
+
+/// Accepts install requests on a localhost port until the process is stopped.
+pub async fn serve(port: u16) -> anyhow::Result<()> {
+    // Only ever loopback, anyone on the network could otherwise install anywhere the user can write
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .await
+        .with_context(|| format!("Failed to listen on port {}", port))?;
+
+    println!("Listening on {}", listener.local_addr()?);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        tracing::info!(peer = ?peer, "Accepted connection");
+
+        tokio::spawn(async move {
+            if let Err(err) = handle(stream).await {
+                tracing::warn!(peer = ?peer, error = ?err, "Connection failed");
+            }
+        });
+    }
+}
+
+/// Runs the requests of one connection in the order they arrive.
+async fn handle(stream: TcpStream) -> anyhow::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                let message = format!("Invalid request: {}", err);
+                send(&mut write, &Event::Error { message }).await?;
+
+                continue;
+            }
+        };
+
+        let args = request.into_args();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+
+        let install = async {
+            let resolved = super::resolve(&args).await?;
+
+            super::install_into(
+                &args,
+                &args.dir[0],
+                Some(&resolved),
+                None,
+                Reporter::new(sender),
+            )
+            .await
+        };
+        tokio::pin!(install);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut install => break result,
+                Some(progress) = receiver.recv() => {
+                    let event = Event::Progress { step: progress.step, fraction: progress.fraction };
+                    send(&mut write, &event).await?;
+                }
+            }
+        };
+
+        // Whatever was reported right before the install finished is still queued
+        while let Ok(progress) = receiver.try_recv() {
+            let event = Event::Progress {
+                step: progress.step,
+                fraction: progress.fraction,
+            };
+            send(&mut write, &event).await?;
+        }
+
+        let event = match result {
+            Ok(report) => Event::Done { report },
+            Err(err) => Event::Error {
+                message: format!("{:#}", err),
+            },
+        };
+        send(&mut write, &event).await?;
+    }
+
+    Ok(())
+}
+
+async fn send(write: &mut OwnedWriteHalf, event: &Event) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+
+    write.write_all(line.as_bytes()).await?;
+
+    Ok(())
+}
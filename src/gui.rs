@@ -1,31 +1,79 @@
-use std::path::PathBuf;
+pub mod autocomplete;
+mod style;
 
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use futures::StreamExt;
 use iced::{
     alignment::Horizontal,
-    button, executor, text_input,
+    button, executor, keyboard, pick_list, scrollable, text_input, time,
     window::{self, Icon},
-    Alignment, Application, Button, Checkbox, Column, Command, Element, Length, ProgressBar, Row,
-    Rule, Settings, Text, TextInput,
+    Alignment, Application, Button, Checkbox, Column, Command, Element, Length, PickList,
+    ProgressBar, Row, Rule, Scrollable, Settings, Subscription, Text, TextInput,
 };
 use iced_aw::{TabLabel, Tabs};
-use iced_native::command::Action;
-use native_dialog::FileDialog;
-use png::Transformations;
+use iced_native::{command::Action, Event};
+use native_dialog::{FileDialog, MessageDialog, MessageType};
+use png::{ColorType, Transformations};
 
 use crate::{
-    loaders::{self, fabric, forge, minecraft, quilt, Loader, LoaderVersion, Side},
-    utils, ICON,
+    loaders::{
+        self, fabric, forge, maven::Mirrors, minecraft, quilt, urls::BaseUrls, DownloadEstimate,
+        ErrorKind, ExistingProfile, Install, InstallEvent, JarCompression, Loader, LoaderVersion,
+        Side,
+    },
+    presets::{Preset, Presets},
+    settings,
+    updates::{self, Release},
+    utils::{
+        self,
+        i18n::{self, tr, Language},
+        java::{self, Java},
+    },
+    ICON,
 };
 
-pub fn run(args: crate::Args) -> anyhow::Result<()> {
+pub fn run(args: crate::Args, crash_log: Option<PathBuf>) -> anyhow::Result<()> {
+    if let Some(path) = crash_log {
+        let text = format!(
+            "The installer crashed last time it was run, a crash report was saved to:\n\n{}",
+            path.display()
+        );
+
+        let result = MessageDialog::new()
+            .set_type(MessageType::Warning)
+            .set_title("anymc-installer")
+            .set_text(&text)
+            .show_alert();
+        if let Err(err) = result {
+            tracing::warn!(error = ?err, "Failed to show crash report dialog");
+        }
+    }
+
+    // A broken icon isn't worth refusing to start over
+    let icon = match create_icon() {
+        Ok(icon) => Some(icon),
+        Err(err) => {
+            tracing::warn!(error = ?err, "Failed to load window icon, starting without one");
+            None
+        }
+    };
+
     let settings = Settings {
         flags: args,
         window: window::Settings {
-            size: (600, 300),
+            size: (600, 450),
             resizable: false,
-            icon: Some(create_icon()?),
+            icon,
             ..Default::default()
         },
+        // Closing is handled in `update` so a running install can be cleaned up first
+        exit_on_close_request: false,
         ..Default::default()
     };
 
@@ -33,18 +81,44 @@ pub fn run(args: crate::Args) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Whether there's a display to open the window on, only X11 and Wayland sessions can lack one.
+pub fn has_display() -> bool {
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| std::env::var_os(var).map_or(false, |value| !value.is_empty()))
+    } else {
+        true
+    }
+}
+
 fn create_icon() -> anyhow::Result<Icon> {
     let mut decoder = png::Decoder::new(ICON);
     decoder.set_transformations(Transformations::EXPAND);
 
-    let mut reader = decoder.read_info()?;
+    let mut reader = decoder
+        .read_info()
+        .context("Window icon is not a valid PNG")?;
 
     let mut buffer = vec![0; reader.output_buffer_size()];
-    let info = reader.next_frame(&mut buffer)?;
+    let info = reader
+        .next_frame(&mut buffer)
+        .context("Failed to decode window icon")?;
 
     let bytes = &buffer[..info.buffer_size()];
 
-    let icon = Icon::from_rgba(bytes.to_vec(), info.width, info.height)?;
+    // Expanding leaves images without an alpha channel as RGB, the window icon needs RGBA
+    let rgba = match info.color_type {
+        ColorType::Rgba => bytes.to_vec(),
+        ColorType::Rgb => bytes
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+            .collect(),
+        other => anyhow::bail!("Window icon has an unsupported color type: {:?}", other),
+    };
+
+    let icon = Icon::from_rgba(rgba, info.width, info.height)
+        .context("Window icon has invalid dimensions")?;
 
     Ok(icon)
 }
@@ -56,13 +130,32 @@ enum Message {
     Error(anyhow::Error),
 
     BrowseLocation,
+    LocationChecked(u64, anyhow::Result<()>),
+    ProfilesFound(u64, anyhow::Result<Vec<ExistingProfile>>),
 
     Fabric(fabric::Message),
     Forge(forge::Message),
     Quilt(quilt::Message),
 
+    SetRelease(anyhow::Result<Release>),
+    RefreshVersions,
+    JavaFound(anyhow::Result<Java>),
+
     Install,
-    InstallDone(anyhow::Result<()>),
+    ConfirmInstall(anyhow::Result<Option<DownloadEstimate>>),
+    InstallEvent(InstallEvent),
+
+    CloseRequested,
+    /// Tab was pressed, moves the keyboard focus to the next text input or back with Shift.
+    FocusNext {
+        backwards: bool,
+    },
+    /// Up or down was pressed, moves through the matches of a focused version input.
+    MoveHighlight {
+        down: bool,
+    },
+    /// Advances the indeterminate progress bar.
+    Pulse,
 }
 
 #[derive(Debug, Clone)]
@@ -70,8 +163,18 @@ enum Interaction {
     SelectLoader(usize),
     SelectSide(usize),
 
+    ShowUnstable(bool),
+    ExpertMode(bool),
+
+    ToggleReleaseNotes,
+    CheckForUpdates(bool),
+    RefreshVersions(bool),
+    CheckUpdatesNow,
+
     BrowseLocation,
     ChangeLocation(PathBuf),
+    SelectProfile(ExistingProfile),
+    Reinstall,
 
     Fabric(fabric::Interaction),
     Forge(forge::Interaction),
@@ -80,6 +183,19 @@ enum Interaction {
     ClientGenerateProfile(bool),
     ServerDownloadJar(bool),
     ServerGenerateScript(bool),
+    ServerAcceptEula(bool),
+
+    ToggleAdvanced,
+    CopyCommand,
+    ClientSkipPlaceholderJar(bool),
+    ClientKeepExisting(bool),
+    ServerCompressJar(bool),
+
+    SelectPreset(String),
+    PresetNameChanged(String),
+    SavePreset,
+    RenamePreset,
+    DeletePreset,
 
     Install,
     InstallPrevent,
@@ -92,8 +208,85 @@ impl Into<Command<Message>> for Message {
     }
 }
 
+/// How often the version lists are fetched again when `refresh_versions` is enabled.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How often the indeterminate progress bar moves.
+const PULSE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How far along the running install is, as far as that can be told.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InstallProgress {
+    /// Waiting on something without a known size, shown as a bar sweeping back and forth.
+    Indeterminate,
+    Fraction(f32),
+}
+
+impl Default for InstallProgress {
+    fn default() -> Self {
+        InstallProgress::Fraction(0.0)
+    }
+}
+
+/// A recently used install directory, as offered in the directory row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecentDir(PathBuf);
+
+impl std::fmt::Display for RecentDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+/// The name clap accepts for a value of an enum argument.
+fn value_name(value: impl clap::ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// The variant a tab stands for, tabs are laid out in declaration order.
+///
+/// `None` for an index past the last variant.
+fn from_tab<T: clap::ValueEnum + Clone>(tab: usize) -> Option<T> {
+    let value = T::value_variants().get(tab).cloned();
+    if value.is_none() {
+        tracing::warn!(tab, "Ignoring selection of an unknown tab");
+    }
+
+    value
+}
+
+/// Quotes an argument for the platform's usual shell if it has anything a shell would split on.
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=+,@".contains(c));
+    if plain {
+        return Cow::Borrowed(arg);
+    }
+
+    if cfg!(target_os = "windows") {
+        Cow::Owned(format!("\"{}\"", arg.replace('"', "\\\"")))
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', "'\\''")))
+    }
+}
+
+/// The status line for a failed install, with a hint on what to try if the cause is known.
+fn failure_status(err: &anyhow::Error) -> String {
+    match ErrorKind::of(err).hint() {
+        Some(hint) => format!("{}: {:#}\n{}", tr("install_failed"), err, tr(hint)),
+        None => format!("{}: {:#}", tr("install_failed"), err),
+    }
+}
+
 #[derive(Debug, Default)]
 struct State {
+    settings: settings::Settings,
+
     selected_loader: Loader,
     selected_side: Side,
 
@@ -104,15 +297,522 @@ struct State {
     install_location_input: text_input::State,
     install_location: PathBuf,
     install_location_browse: button::State,
+    recent_pick_list: pick_list::State<RecentDir>,
+    /// Bumped on every change so only the newest location check is applied.
+    location_check: u64,
+    location_valid: Option<bool>,
+    /// Variables in the typed location that aren't set, they're left as typed.
+    location_unknown_vars: Vec<String>,
+
+    /// The Java runtime installs run with, `None` while it's still being searched for.
+    java: Option<anyhow::Result<Java>>,
+
+    /// Earlier client installs in the install location, for reinstalling them in one click.
+    existing_profiles: Vec<ExistingProfile>,
+    existing_pick_list: pick_list::State<ExistingProfile>,
+    selected_profile: Option<ExistingProfile>,
+    reinstall_button: button::State,
 
     client_generate_profile: bool,
 
     server_download_jar: bool,
     server_generate_script: bool,
+    server_accept_eula: bool,
+
+    advanced_button: button::State,
+    copy_command_button: button::State,
+    client_skip_placeholder_jar: bool,
+    client_keep_existing: bool,
+    server_compress_jar: bool,
+
+    presets: Presets,
+    preset_pick_list: pick_list::State<String>,
+    selected_preset: Option<String>,
+    preset_name_input: text_input::State,
+    preset_name: String,
+    save_preset_button: button::State,
+    rename_preset_button: button::State,
+    delete_preset_button: button::State,
+
+    custom_icon: Option<PathBuf>,
+    urls: BaseUrls,
+
+    latest_release: Option<Release>,
+    show_release_notes: bool,
+    release_notes_button: button::State,
+    release_notes_scroll: scrollable::State,
+    release_notes_close: button::State,
+    update_button: button::State,
 
     install_button: button::State,
     install_running: bool,
-    install_progress: f32,
+    install_progress: InstallProgress,
+    /// Ticks of the indeterminate progress bar, it sweeps once every 40.
+    install_pulse: u32,
+    install_status: String,
+    /// When the running install started and how much it downloaded since, for its speed.
+    install_started: Option<Instant>,
+    install_downloaded: u64,
+    /// The launcher profile name of the running install, shown once it's done.
+    install_target: String,
+    /// The running install, its subscription runs it and is keyed by `install_count` so every
+    /// install gets a new one. Taking it away drops the subscription, cancelling the install.
+    install_job: Option<Install<LoaderVersion>>,
+    install_count: u64,
+
+    exiting: bool,
+}
+
+impl State {
+    fn apply_show_unstable(&mut self, show: bool) {
+        self.settings.show_unstable = show;
+
+        self.fabric.set_show_unstable(show);
+        self.forge.set_show_unstable(show);
+        self.quilt.set_show_unstable(show);
+    }
+
+    /// Clients default to `.minecraft` while servers get a fresh folder next to the installer.
+    fn default_location(side: Side) -> PathBuf {
+        match side {
+            Side::Client => utils::get_minecraft_directory().unwrap_or_default(),
+            Side::Server => std::env::current_dir()
+                .map(|dir| dir.join("server"))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn found_java(&self) -> Option<&Java> {
+        self.java.as_ref()?.as_ref().ok()
+    }
+
+    /// Why the found Java can't run a server of the selected version, if it can't.
+    fn java_warning(&self) -> Option<String> {
+        let java = self.found_java()?;
+        let (_, minecraft_version) = self.selected_versions().ok()?;
+
+        let required = minecraft_version.java_version();
+        if self.selected_side != Side::Server || java.supports(required) {
+            return None;
+        }
+
+        Some(format!("{} Java {}", tr("java_too_old"), required))
+    }
+
+    fn selected_versions(&self) -> anyhow::Result<(LoaderVersion, minecraft::Version)> {
+        let minecraft_version = match self.selected_loader {
+            Loader::Fabric => self.fabric.selected_minecraft(),
+            Loader::Forge => self.forge.selected_minecraft(),
+            Loader::Quilt => self.quilt.selected_minecraft(),
+        };
+        let minecraft_version = if let Some(version) = minecraft_version {
+            version
+        } else {
+            anyhow::bail!("No Minecraft version selected!");
+        };
+
+        let loader_version = match self.selected_loader {
+            Loader::Fabric => self.fabric.selected_version().map(LoaderVersion::Fabric),
+            Loader::Forge => self.forge.selected_version().map(LoaderVersion::Forge),
+            Loader::Quilt => self.quilt.selected_version().map(LoaderVersion::Quilt),
+        };
+        let loader_version = if let Some(version) = loader_version {
+            version
+        } else {
+            anyhow::bail!("No Loader version selected!");
+        };
+
+        Ok((loader_version, minecraft_version))
+    }
+
+    /// The headless command line that installs the current selection, for sharing it.
+    fn cli_command(&self) -> anyhow::Result<String> {
+        let (loader_version, minecraft_version) = self.selected_versions()?;
+
+        let mut args = vec![
+            "anymc-installer".to_string(),
+            "--no-gui".to_string(),
+            "--loader".to_string(),
+            value_name(self.selected_loader),
+            "--side".to_string(),
+            value_name(self.selected_side),
+            "--dir".to_string(),
+            self.install_location.display().to_string(),
+            "--minecraft".to_string(),
+            minecraft_version.to_string(),
+            "--loader-version".to_string(),
+            loader_version.to_string(),
+        ];
+
+        let flags = match self.selected_side {
+            Side::Client => vec![
+                ("--no-profile", !self.client_generate_profile),
+                ("--no-placeholder-jar", self.client_skip_placeholder_jar),
+                ("--keep-existing", self.client_keep_existing),
+            ],
+            Side::Server => vec![
+                ("--download-server", self.server_download_jar),
+                ("--create-scripts", self.server_generate_script),
+                ("--accept-eula", self.server_accept_eula),
+            ],
+        };
+        args.extend(
+            flags
+                .into_iter()
+                .filter(|(_, set)| *set)
+                .map(|(flag, _)| flag.to_string()),
+        );
+
+        if self.selected_side == Side::Server && self.server_compress_jar {
+            args.push("--jar-compression".to_string());
+            args.push(value_name(JarCompression::Deflated));
+        }
+        if let Some(icon) = &self.custom_icon {
+            args.push("--icon".to_string());
+            args.push(icon.display().to_string());
+        }
+
+        // Legacy Fabric has services of its own, the command line only reaches them by URL
+        if self.selected_loader == Loader::Fabric {
+            let urls = self.base_urls();
+            if urls.fabric_meta != fabric::META {
+                args.push("--fabric-meta-url".to_string());
+                args.push(urls.fabric_meta);
+            }
+            if urls.fabric_maven != fabric::MAVEN {
+                args.push("--maven-mirror".to_string());
+                args.push(urls.fabric_maven);
+            }
+        }
+
+        Ok(args
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    fn start_install(
+        &mut self,
+        loader_version: LoaderVersion,
+        minecraft_version: minecraft::Version,
+    ) -> Command<Message> {
+        let install_target = format!("{}-{}", loader_version.name(), minecraft_version);
+
+        let install = Install::builder()
+            .version(loader_version)
+            .side(self.selected_side)
+            .dir(self.install_location.clone())
+            .minecraft(minecraft_version)
+            .generate_profile(self.client_generate_profile)
+            .placeholder_jar(!self.client_skip_placeholder_jar)
+            .keep_existing(self.client_keep_existing)
+            .jar_compression(if self.server_compress_jar {
+                JarCompression::Deflated
+            } else {
+                JarCompression::Stored
+            })
+            .generate_script(self.server_generate_script)
+            .download_jar(self.server_download_jar)
+            .accept_eula(self.server_accept_eula)
+            .icon(self.custom_icon.clone())
+            .urls(self.base_urls())
+            .build();
+        let install = match install {
+            Ok(install) => install,
+            Err(err) => return Message::Error(err).into(),
+        };
+
+        self.install_target = install_target;
+        self.install_status = tr("starting_install").to_string();
+        self.install_progress = InstallProgress::Indeterminate;
+        self.install_running = true;
+        self.install_started = Some(Instant::now());
+        self.install_downloaded = 0;
+
+        self.install_job = Some(install);
+        self.install_count += 1;
+
+        Command::none()
+    }
+
+    /// Why the install button is disabled, the first unmet precondition of an install.
+    fn install_blocker(&self) -> Option<&'static str> {
+        // A failed fetch is already explained by the loader's status row
+        let (loading, failed, minecraft, version) = match self.selected_loader {
+            Loader::Fabric => (
+                self.fabric.versions.is_empty() || self.fabric.minecraft.versions.is_empty(),
+                self.fabric.status.is_some(),
+                self.fabric.selected_minecraft().is_some(),
+                self.fabric.selected_version().is_some(),
+            ),
+            Loader::Forge => (
+                self.forge.versions.is_empty(),
+                self.forge.status.is_some(),
+                self.forge.selected_minecraft().is_some(),
+                self.forge.selected_version().is_some(),
+            ),
+            Loader::Quilt => (
+                self.quilt.versions.is_empty() || self.quilt.minecraft.versions.is_empty(),
+                self.quilt.status.is_some(),
+                self.quilt.selected_minecraft().is_some(),
+                self.quilt.selected_version().is_some(),
+            ),
+        };
+
+        if failed {
+            Some(tr("blocked_no_versions"))
+        } else if loading {
+            Some(tr("blocked_loading"))
+        } else if !minecraft {
+            Some(tr("blocked_no_minecraft"))
+        } else if !version {
+            Some(tr("blocked_no_loader"))
+        } else if self.location_valid == Some(false) {
+            Some(tr("blocked_location"))
+        } else {
+            None
+        }
+    }
+
+    /// The base URLs, with Fabric's pointed at the flavor picked in its tab.
+    fn base_urls(&self) -> BaseUrls {
+        self.fabric.urls(&self.urls)
+    }
+
+    fn fetch_versions(&self) -> Command<Message> {
+        Command::batch([
+            fabric::State::fetch(&self.base_urls()).map(Message::Fabric),
+            forge::State::fetch(&self.urls).map(Message::Forge),
+            quilt::State::fetch(&self.urls).map(Message::Quilt),
+        ])
+    }
+
+    fn update_available(&self) -> bool {
+        self.latest_release
+            .as_ref()
+            .map(Release::is_newer)
+            .unwrap_or(false)
+    }
+
+    #[rustfmt::skip]
+    fn release_notes_view(&mut self) -> Element<'_, Interaction> {
+        let (title, notes) = match &self.latest_release {
+            Some(release) if release.is_newer() => (
+                format!("anymc-installer {} is available", release.tag_name),
+                release.body.clone().unwrap_or_default(),
+            ),
+            _ => (
+                format!("anymc-installer {}", env!("CARGO_PKG_VERSION")),
+                updates::CHANGELOG.to_string(),
+            ),
+        };
+
+        Column::new()
+            .padding(5)
+            .spacing(5)
+            .push(Text::new(title).size(24))
+            .push(Scrollable::new(&mut self.release_notes_scroll)
+                .height(Length::Fill)
+                .push(Text::new(notes)))
+            .push(Row::new()
+                .push(Checkbox::new(self.settings.check_for_updates, tr("check_for_updates"), Interaction::CheckForUpdates))
+                .push(Checkbox::new(self.settings.refresh_versions, tr("refresh_versions"), Interaction::RefreshVersions))
+                .push(Button::new(&mut self.update_button, Text::new(tr("check_now"))).on_press(Interaction::CheckUpdatesNow))
+                .push(Button::new(&mut self.release_notes_close, Text::new(tr("close"))).on_press(Interaction::ToggleReleaseNotes))
+                .align_items(Alignment::Center)
+                .spacing(5))
+            .into()
+    }
+
+    /// Replaces variables in the typed location with their values, once it's used.
+    fn expand_location(&mut self) {
+        let (dir, unknown) = utils::expand_path(&self.install_location);
+        if !unknown.is_empty() {
+            tracing::warn!(unknown = ?unknown, "Install location has unknown variables");
+        }
+
+        self.install_location = dir;
+        self.location_unknown_vars = unknown;
+    }
+
+    fn remember_location(&mut self) {
+        let location = Some(self.install_location.clone());
+
+        match self.selected_side {
+            Side::Client => self.settings.client_dir = location,
+            Side::Server => self.settings.server_dir = location,
+        }
+    }
+
+    /// Checks the install location once typing pauses, so every keystroke doesn't hit the disk.
+    fn check_location(&mut self) -> Command<Message> {
+        self.location_check += 1;
+        self.location_valid = None;
+
+        // The field itself is only expanded on install, `$HOME_DIR` would otherwise turn into
+        // `/home/me_DIR` as soon as `$HOME` was typed
+        let (dir, unknown) = utils::expand_path(&self.install_location);
+        self.location_unknown_vars = unknown;
+
+        let check = self.location_check;
+        let side = self.selected_side;
+
+        let location = Command::perform(
+            {
+                let dir = dir.clone();
+
+                async move {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    utils::check_install_location(side, &dir).await
+                }
+            },
+            move |result| Message::LocationChecked(check, result),
+        );
+
+        // Only client installs leave profiles behind to pick from
+        if side == Side::Server {
+            self.existing_profiles.clear();
+            self.selected_profile = None;
+
+            return location;
+        }
+
+        let profiles = Command::perform(
+            async move {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                loaders::existing_profiles(dir).await
+            },
+            move |result| Message::ProfilesFound(check, result),
+        );
+
+        Command::batch([location, profiles])
+    }
+
+    /// Fills in the loader and versions of an earlier install, `false` if they're gone.
+    fn apply_profile(&mut self, profile: &ExistingProfile) -> bool {
+        self.selected_loader = profile.loader;
+
+        self.select_existing(profile.loader, &profile.version, &profile.minecraft)
+    }
+
+    fn select_existing(&mut self, loader: Loader, version: &str, minecraft: &str) -> bool {
+        match loader {
+            Loader::Fabric => self.fabric.select_existing(version, minecraft),
+            Loader::Quilt => self.quilt.select_existing(version, minecraft),
+            Loader::Forge => false,
+        }
+    }
+
+    /// The current selection and options as a preset, without versions if none are selected.
+    fn current_preset(&self, name: &str) -> Preset {
+        let versions = self.selected_versions().ok();
+
+        Preset {
+            name: name.to_string(),
+            loader: self.selected_loader,
+            side: self.selected_side,
+            dir: Some(self.install_location.clone()),
+            minecraft: versions
+                .as_ref()
+                .map(|(_, minecraft)| minecraft.to_string()),
+            loader_version: versions.as_ref().map(|(version, _)| version.to_string()),
+            no_profile: !self.client_generate_profile,
+            no_placeholder_jar: self.client_skip_placeholder_jar,
+            keep_existing: self.client_keep_existing,
+            download_server: self.server_download_jar,
+            create_scripts: self.server_generate_script,
+            compress_jar: self.server_compress_jar,
+            accept_eula: self.server_accept_eula,
+        }
+    }
+
+    /// Switches to the selection and options of a preset, its versions if they're still listed.
+    fn apply_preset(&mut self, preset: &Preset) -> Command<Message> {
+        self.remember_location();
+        self.selected_loader = preset.loader;
+        self.selected_side = preset.side;
+        self.restore_location();
+        if let Some(dir) = &preset.dir {
+            self.install_location = dir.clone();
+        }
+
+        self.client_generate_profile = !preset.no_profile;
+        self.client_skip_placeholder_jar = preset.no_placeholder_jar;
+        self.client_keep_existing = preset.keep_existing;
+        self.server_download_jar = preset.download_server;
+        self.server_generate_script = preset.create_scripts;
+        self.server_compress_jar = preset.compress_jar;
+        self.server_accept_eula = preset.accept_eula;
+
+        if let (Some(version), Some(minecraft)) = (&preset.loader_version, &preset.minecraft) {
+            if !self.select_existing(preset.loader, version, minecraft) {
+                self.install_status = format!("{}: {}", tr("preset_unavailable"), preset);
+            }
+        }
+
+        self.check_location()
+    }
+
+    /// Looks up the loader builds supporting the selected Minecraft version, if it's a snapshot.
+    fn fetch_supported(&self) -> Command<Message> {
+        match self.selected_loader {
+            Loader::Fabric => self
+                .fabric
+                .fetch_supported(&self.base_urls())
+                .map(Message::Fabric),
+            Loader::Quilt => self.quilt.fetch_supported(&self.urls).map(Message::Quilt),
+            Loader::Forge => Command::none(),
+        }
+    }
+
+    /// Moves the focus through the text inputs on screen in the order they're laid out.
+    ///
+    /// iced doesn't move the focus on its own, and text inputs are the only widgets that can
+    /// take it.
+    fn focus_next(&mut self, backwards: bool) {
+        let expert = self.settings.expert_mode;
+        let mut inputs = match self.selected_loader {
+            Loader::Fabric => self.fabric.text_inputs(expert),
+            Loader::Forge => self.forge.text_inputs(expert),
+            Loader::Quilt => self.quilt.text_inputs(expert),
+        };
+        inputs.push(&mut self.install_location_input);
+        if self.settings.show_advanced {
+            inputs.push(&mut self.preset_name_input);
+        }
+
+        let count = inputs.len();
+        let next = match inputs.iter().position(|input| input.is_focused()) {
+            Some(focused) if backwards => (focused + count - 1) % count,
+            Some(focused) => (focused + 1) % count,
+            None if backwards => count - 1,
+            None => 0,
+        };
+
+        for input in inputs.iter_mut() {
+            input.unfocus();
+        }
+        inputs[next].focus();
+        inputs[next].move_cursor_to_end();
+    }
+
+    fn save_presets(&mut self) -> Command<Message> {
+        match self.presets.save() {
+            Ok(()) => Command::none(),
+            Err(err) => Message::Error(err).into(),
+        }
+    }
+
+    fn restore_location(&mut self) {
+        let location = match self.selected_side {
+            Side::Client => self.settings.client_dir.clone(),
+            Side::Server => self.settings.server_dir.clone(),
+        };
+
+        self.install_location =
+            location.unwrap_or_else(|| Self::default_location(self.selected_side));
+    }
 }
 
 impl Application for State {
@@ -123,53 +823,368 @@ impl Application for State {
     type Flags = crate::Args;
 
     #[rustfmt::skip]
-    fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
+    fn new(flags: Self::Flags) -> (Self, Command<Self::Message>) {
+        let mut state = Self {
+            settings: settings::Settings::load(),
+            presets: Presets::load(),
+            urls: flags.base_urls(),
+            custom_icon: flags.icon,
+            ..Default::default()
+        };
+        i18n::set_language(
+            flags
+                .lang
+                .or(state.settings.language)
+                .unwrap_or_else(Language::from_system),
+        );
+        state.apply_show_unstable(state.settings.show_unstable && state.settings.expert_mode);
+        state.restore_location();
+
+        let update_check = if state.settings.check_for_updates {
+            Command::perform(updates::fetch_latest(), Message::SetRelease)
+        } else {
+            Command::none()
+        };
+
+        let location_check = state.check_location();
+        let fetch_versions = state.fetch_versions();
+        let java_search = Command::perform(java::find(), Message::JavaFound);
+
         (
-            Self {
-                install_location: utils::get_minecraft_directory().unwrap_or_default(),
-                ..Default::default()
-            },
+            state,
             Command::batch([
-                Command::perform(fabric::Commands::fetch_minecraft(), fabric::Message::SetMinecraft).map(Message::Fabric),
-                Command::perform(fabric::Commands::fetch_versions(), fabric::Message::SetVersions).map(Message::Fabric),
-
-                Command::perform(quilt::Commands::fetch_minecraft(), quilt::Message::SetMinecraft).map(Message::Quilt),
-                Command::perform(quilt::Commands::fetch_versions(), quilt::Message::SetVersions).map(Message::Quilt),
+                update_check,
+                location_check,
+                fetch_versions,
+                java_search,
             ]),
         )
     }
 
     fn title(&self) -> String {
-        "anymc-installer".to_string()
+        // Shows up in the taskbar, so progress can be followed while the window is minimized
+        match (self.install_running, self.install_progress) {
+            (true, InstallProgress::Fraction(fraction)) => {
+                format!("anymc-installer \u{2014} {:.0}%", fraction * 100.0)
+            }
+            _ => "anymc-installer".to_string(),
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        let close = iced_native::subscription::events_with(|event, status| match event {
+            Event::Window(iced_native::window::Event::CloseRequested) => {
+                Some(Message::CloseRequested)
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Tab,
+                modifiers,
+            }) if status == iced_native::event::Status::Ignored => Some(Message::FocusNext {
+                backwards: modifiers.shift(),
+            }),
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: key_code @ (keyboard::KeyCode::Up | keyboard::KeyCode::Down),
+                ..
+            }) if status == iced_native::event::Status::Ignored => Some(Message::MoveHighlight {
+                down: key_code == keyboard::KeyCode::Down,
+            }),
+            _ => None,
+        });
+
+        let mut subscriptions = vec![close];
+        if self.settings.refresh_versions {
+            subscriptions.push(time::every(REFRESH_INTERVAL).map(|_| Message::RefreshVersions));
+        }
+        // Only ticks while there's an indeterminate bar to animate
+        if self.install_running && self.install_progress == InstallProgress::Indeterminate {
+            subscriptions.push(time::every(PULSE_INTERVAL).map(|_| Message::Pulse));
+        }
+        if let Some(install) = &self.install_job {
+            let events = loaders::install_events(install.clone()).map(Message::InstallEvent);
+            subscriptions.push(iced_native::subscription::run(self.install_count, events));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exiting
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         match message {
             Message::Interaction(interaction) => match interaction {
-                Interaction::SelectLoader(tab) => self.selected_loader = Loader::from(tab as u8),
-                Interaction::SelectSide(tab) => self.selected_side = Side::from(tab as u8),
+                Interaction::SelectLoader(tab) => {
+                    let loader = match from_tab::<Loader>(tab) {
+                        Some(loader) => loader,
+                        None => return Command::none(),
+                    };
+
+                    let previous = match self.selected_loader {
+                        Loader::Fabric => &self.fabric.minecraft,
+                        Loader::Forge => &self.forge.minecraft,
+                        Loader::Quilt => &self.quilt.minecraft,
+                    };
+                    let versions = previous.versions.clone();
+                    let selected = previous.selected_version.clone();
+
+                    // Keep the Minecraft version when the new loader supports it
+                    self.selected_loader = loader;
+                    match self.selected_loader {
+                        Loader::Fabric => self
+                            .fabric
+                            .carry_over_minecraft(&versions, selected.as_ref()),
+                        Loader::Forge => self
+                            .forge
+                            .carry_over_minecraft(&versions, selected.as_ref()),
+                        Loader::Quilt => self
+                            .quilt
+                            .carry_over_minecraft(&versions, selected.as_ref()),
+                    }
+
+                    return self.fetch_supported();
+                }
+                Interaction::SelectSide(tab) => {
+                    let side = match from_tab::<Side>(tab) {
+                        Some(side) => side,
+                        None => return Command::none(),
+                    };
+
+                    self.remember_location();
+                    self.selected_side = side;
+                    self.restore_location();
+
+                    if let Err(err) = self.settings.save() {
+                        return Message::Error(err).into();
+                    }
+
+                    return self.check_location();
+                }
+                Interaction::SelectProfile(profile) => {
+                    if !self.apply_profile(&profile) {
+                        self.install_status = format!("{}: {}", tr("profile_unavailable"), profile);
+                    }
+
+                    self.selected_profile = Some(profile);
+                }
+                Interaction::Reinstall => {
+                    if let Some(profile) = self.selected_profile.clone() {
+                        if self.apply_profile(&profile) {
+                            return Message::Install.into();
+                        }
+
+                        self.install_status = format!("{}: {}", tr("profile_unavailable"), profile);
+                    }
+                }
+
+                // The Minecraft filter is shared too, anything past releases counts as unstable
+                Interaction::Fabric(fabric::Interaction::Minecraft(
+                    minecraft::Interaction::SelectFilter(filter),
+                ))
+                | Interaction::Forge(forge::Interaction::Minecraft(
+                    minecraft::Interaction::SelectFilter(filter),
+                ))
+                | Interaction::Quilt(quilt::Interaction::Minecraft(
+                    minecraft::Interaction::SelectFilter(filter),
+                )) => {
+                    self.apply_show_unstable(filter != minecraft::VersionFilter::Releases);
+
+                    self.fabric.minecraft.filter = filter;
+                    self.forge.minecraft.filter = filter;
+                    self.quilt.minecraft.filter = filter;
+
+                    if let Err(err) = self.settings.save() {
+                        return Message::Error(err).into();
+                    }
+                }
+
+                Interaction::ExpertMode(expert) => {
+                    self.settings.expert_mode = expert;
+
+                    // Simple mode installs what it names, nothing unstable picked in expert mode
+                    if !expert {
+                        self.apply_show_unstable(false);
+
+                        self.fabric.select_recommended();
+                        self.forge.select_recommended();
+                        self.quilt.select_recommended();
+                    }
+
+                    if let Err(err) = self.settings.save() {
+                        return Message::Error(err).into();
+                    }
+                }
+
+                // Every unstable toggle drives the same setting so they never disagree
+                Interaction::ShowUnstable(show)
+                | Interaction::Fabric(fabric::Interaction::ShowBetas(show))
+                | Interaction::Quilt(quilt::Interaction::ShowBetas(show)) => {
+                    self.apply_show_unstable(show);
+
+                    if let Err(err) = self.settings.save() {
+                        return Message::Error(err).into();
+                    }
+                }
+
+                Interaction::ToggleReleaseNotes => {
+                    self.show_release_notes = !self.show_release_notes
+                }
+                Interaction::CheckForUpdates(enable) => {
+                    self.settings.check_for_updates = enable;
+
+                    if let Err(err) = self.settings.save() {
+                        return Message::Error(err).into();
+                    }
+                }
+                Interaction::RefreshVersions(enable) => {
+                    self.settings.refresh_versions = enable;
+
+                    if let Err(err) = self.settings.save() {
+                        return Message::Error(err).into();
+                    }
+                }
+                Interaction::CheckUpdatesNow => {
+                    return Command::perform(updates::fetch_latest(), Message::SetRelease)
+                }
 
                 Interaction::BrowseLocation => return Message::BrowseLocation.into(),
-                Interaction::ChangeLocation(location) => self.install_location = location,
+                Interaction::ChangeLocation(location) => {
+                    self.install_location = location;
+
+                    return self.check_location();
+                }
+
+                // The loaders only clear their status, fetching needs the base URLs
+                Interaction::Fabric(fabric::Interaction::Refresh) => {
+                    self.fabric.update_interaction(fabric::Interaction::Refresh);
+
+                    return fabric::State::fetch(&self.base_urls()).map(Message::Fabric);
+                }
+                Interaction::Fabric(fabric::Interaction::SelectFlavor(flavor)) => {
+                    self.fabric
+                        .update_interaction(fabric::Interaction::SelectFlavor(flavor));
+
+                    return fabric::State::fetch(&self.base_urls()).map(Message::Fabric);
+                }
+                Interaction::Forge(forge::Interaction::Refresh) => {
+                    self.forge.update_interaction(forge::Interaction::Refresh);
+
+                    return forge::State::fetch(&self.urls).map(Message::Forge);
+                }
+                Interaction::Quilt(quilt::Interaction::Refresh) => {
+                    self.quilt.update_interaction(quilt::Interaction::Refresh);
+
+                    return quilt::State::fetch(&self.urls).map(Message::Quilt);
+                }
 
                 Interaction::Fabric(message) => {
-                    return self.fabric.update_interaction(message).map(Message::Fabric)
+                    let command = self.fabric.update_interaction(message).map(Message::Fabric);
+
+                    // A snapshot may have been picked, which only some loader builds support
+                    return Command::batch([command, self.fetch_supported()]);
                 }
                 Interaction::Forge(message) => {
                     return self.forge.update_interaction(message).map(Message::Forge)
                 }
                 Interaction::Quilt(message) => {
-                    return self.quilt.update_interaction(message).map(Message::Quilt)
+                    let command = self.quilt.update_interaction(message).map(Message::Quilt);
+
+                    // A snapshot may have been picked, which only some loader builds support
+                    return Command::batch([command, self.fetch_supported()]);
                 }
 
                 Interaction::ClientGenerateProfile(enable) => self.client_generate_profile = enable,
                 Interaction::ServerDownloadJar(enable) => self.server_download_jar = enable,
                 Interaction::ServerGenerateScript(enable) => self.server_generate_script = enable,
+                Interaction::ServerAcceptEula(enable) => self.server_accept_eula = enable,
+
+                Interaction::ToggleAdvanced => {
+                    self.settings.show_advanced = !self.settings.show_advanced;
+
+                    if let Err(err) = self.settings.save() {
+                        return Message::Error(err).into();
+                    }
+                }
+                Interaction::ClientSkipPlaceholderJar(enable) => {
+                    self.client_skip_placeholder_jar = enable
+                }
+                Interaction::ClientKeepExisting(enable) => self.client_keep_existing = enable,
+                Interaction::CopyCommand => match self.cli_command() {
+                    Ok(command) => {
+                        self.install_status = tr("command_copied").to_string();
+
+                        return iced::clipboard::write(command);
+                    }
+                    Err(err) => return Message::Error(err).into(),
+                },
+                Interaction::ServerCompressJar(enable) => self.server_compress_jar = enable,
+
+                Interaction::SelectPreset(name) => {
+                    if let Some(preset) = self.presets.get(&name).cloned() {
+                        self.preset_name = name.clone();
+                        self.selected_preset = Some(name);
+
+                        return self.apply_preset(&preset);
+                    }
+                }
+                Interaction::PresetNameChanged(name) => self.preset_name = name,
+                Interaction::SavePreset => {
+                    let name = self.preset_name.trim().to_string();
+
+                    match self.presets.insert(self.current_preset(&name)) {
+                        Ok(()) => {
+                            self.install_status = format!("{}: {}", tr("preset_saved"), name);
+                            self.selected_preset = Some(name);
+
+                            return self.save_presets();
+                        }
+                        Err(err) => self.install_status = format!("{:#}", err),
+                    }
+                }
+                Interaction::RenamePreset => {
+                    if let Some(from) = self.selected_preset.clone() {
+                        let to = self.preset_name.trim().to_string();
+
+                        match self.presets.rename(&from, &to) {
+                            Ok(()) => {
+                                self.selected_preset = Some(to);
+
+                                return self.save_presets();
+                            }
+                            Err(err) => self.install_status = format!("{:#}", err),
+                        }
+                    }
+                }
+                Interaction::DeletePreset => {
+                    if let Some(name) = self.selected_preset.take() {
+                        self.presets.remove(&name);
+                        self.preset_name.clear();
+
+                        return self.save_presets();
+                    }
+                }
 
                 Interaction::Install => return Message::Install.into(),
                 Interaction::InstallPrevent => {}
             },
             Message::Error(err) => eprintln!("{:#?}", err),
+            Message::SetRelease(result) => match result {
+                Ok(release) => {
+                    self.show_release_notes |= release.is_newer();
+                    self.latest_release = Some(release);
+                }
+                // Being offline shouldn't get in the way of installing
+                Err(err) => tracing::warn!(error = ?err, "Failed to check for updates"),
+            },
+            // The version handlers only pick a default when nothing is selected, so this keeps the
+            // user's selection while adding new builds
+            Message::RefreshVersions => return self.fetch_versions(),
+            Message::JavaFound(result) => {
+                if let Err(err) = &result {
+                    tracing::info!(error = %err, "No Java runtime found");
+                }
+                self.java = Some(result);
+            }
             Message::Fabric(message) => {
                 return self.fabric.update_message(message).map(Message::Fabric)
             }
@@ -190,50 +1205,210 @@ impl Application for State {
                 }
 
                 match dialog.show_open_single_dir() {
-                    Ok(Some(path)) => self.install_location = path,
+                    Ok(Some(path)) => {
+                        self.install_location = path;
+
+                        return self.check_location();
+                    }
                     Ok(None) => (),
                     Err(error) => return Message::Error(error.into()).into(),
                 }
             }
+            Message::LocationChecked(check, result) => {
+                if check == self.location_check {
+                    if let Err(err) = &result {
+                        tracing::debug!(error = ?err, "Install location is not usable");
+                    }
+
+                    self.location_valid = Some(result.is_ok());
+                }
+            }
+            Message::ProfilesFound(check, result) => {
+                if check == self.location_check {
+                    self.existing_profiles = result.unwrap_or_else(|err| {
+                        tracing::debug!(error = ?err, "Failed to look for existing profiles");
+                        Vec::new()
+                    });
+
+                    if !self
+                        .existing_profiles
+                        .iter()
+                        .any(|p| Some(p) == self.selected_profile.as_ref())
+                    {
+                        self.selected_profile = None;
+                    }
+                }
+            }
             Message::Install => {
-                let minecraft_version = match self.selected_loader {
-                    Loader::Fabric => self.fabric.selected_minecraft(),
-                    Loader::Forge => self.forge.selected_minecraft(),
-                    Loader::Quilt => self.quilt.selected_minecraft(),
-                };
-                let minecraft_version = if let Some(version) = minecraft_version {
-                    version
-                } else {
-                    return Message::Error(anyhow::anyhow!("No Minecraft version selected!"))
-                        .into();
+                self.expand_location();
+                self.remember_location();
+                self.settings.add_recent_dir(&self.install_location);
+                if let Err(err) = self.settings.save() {
+                    tracing::warn!(error = ?err, "Failed to save settings");
+                }
+
+                let (loader_version, minecraft_version) = match self.selected_versions() {
+                    Ok(versions) => versions,
+                    Err(err) => return Message::Error(err).into(),
                 };
 
-                let loader_version = match self.selected_loader {
-                    Loader::Fabric => self.fabric.selected_version().map(LoaderVersion::Fabric),
-                    Loader::Forge => self.forge.selected_version().map(LoaderVersion::Forge),
-                    Loader::Quilt => self.quilt.selected_version().map(LoaderVersion::Quilt),
+                if let Some(newer) = loaders::newer_installed(
+                    &self.existing_profiles,
+                    &loader_version,
+                    &minecraft_version.version,
+                ) {
+                    let text = format!(
+                        "{}\n{} \u{2192} {}",
+                        tr("downgrade_confirm"),
+                        newer.version,
+                        loader_version
+                    );
+
+                    let confirmed = MessageDialog::new()
+                        .set_type(MessageType::Warning)
+                        .set_title("anymc-installer")
+                        .set_text(&text)
+                        .show_confirm();
+
+                    match confirmed {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            self.install_status = tr("install_cancelled").to_string();
+                            return Command::none();
+                        }
+                        Err(err) => return Message::Error(err.into()).into(),
+                    }
+                }
+
+                // Servers can download a lot, so say how much before going ahead
+                if self.selected_side == Side::Server {
+                    self.install_status = tr("estimating_download").to_string();
+
+                    return Command::perform(
+                        loaders::estimate_download(
+                            self.base_urls(),
+                            Mirrors::new(&self.base_urls()),
+                            loader_version,
+                            self.selected_side,
+                            self.install_location.clone(),
+                            minecraft_version,
+                        ),
+                        Message::ConfirmInstall,
+                    );
+                }
+
+                return self.start_install(loader_version, minecraft_version);
+            }
+            Message::ConfirmInstall(estimate) => {
+                if let Ok(Some(estimate)) = &estimate {
+                    if let Err(err) =
+                        utils::check_disk_space(&self.install_location, estimate.bytes)
+                    {
+                        self.install_status = failure_status(&err);
+
+                        return Message::Error(err).into();
+                    }
+                }
+
+                let text = match estimate {
+                    Ok(Some(estimate)) => format!(
+                        "This install will download {} of libraries, continue?",
+                        estimate
+                    ),
+                    Ok(None) => "Continue with the install?".to_string(),
+                    Err(err) => {
+                        tracing::warn!(error = ?err, "Failed to estimate download size");
+
+                        "The download size couldn't be determined, continue with the install?"
+                            .to_string()
+                    }
                 };
-                let loader_version = if let Some(version) = loader_version {
-                    version
-                } else {
-                    return Message::Error(anyhow::anyhow!("No Loader version selected!")).into();
+
+                let confirmed = MessageDialog::new()
+                    .set_type(MessageType::Info)
+                    .set_title("anymc-installer")
+                    .set_text(&text)
+                    .show_confirm();
+
+                match confirmed {
+                    Ok(true) => {
+                        let (loader_version, minecraft_version) = match self.selected_versions() {
+                            Ok(versions) => versions,
+                            Err(err) => return Message::Error(err).into(),
+                        };
+
+                        return self.start_install(loader_version, minecraft_version);
+                    }
+                    Ok(false) => self.install_status = tr("install_cancelled").to_string(),
+                    Err(err) => return Message::Error(err.into()).into(),
+                }
+            }
+            Message::Pulse => self.install_pulse = self.install_pulse.wrapping_add(1),
+            Message::InstallEvent(InstallEvent::Progress(progress)) => {
+                self.install_status = progress.step;
+                self.install_downloaded = progress.downloaded;
+                self.install_progress = match progress.fraction {
+                    Some(fraction) => InstallProgress::Fraction(fraction),
+                    None => InstallProgress::Indeterminate,
                 };
+            }
+            Message::InstallEvent(InstallEvent::Finished(result)) => {
+                self.install_running = false;
+                self.install_job = None;
 
-                return Command::perform(
-                    loaders::install(
-                        loader_version,
-                        self.selected_side,
-                        self.install_location.clone(),
-                        minecraft_version,
-                        match self.selected_side {
-                            Side::Client => self.client_generate_profile,
-                            Side::Server => self.server_generate_script,
-                        },
-                    ),
-                    Message::InstallDone,
-                );
+                match result {
+                    Ok(report) => {
+                        let name = report.profile_name.as_ref().unwrap_or(&self.install_target);
+
+                        self.install_progress = InstallProgress::Fraction(1.0);
+                        let done = if report.up_to_date {
+                            tr("already_up_to_date")
+                        } else {
+                            tr("install_complete")
+                        };
+
+                        self.install_status =
+                            format!("{}: {} ({:.1}s)", done, name, report.elapsed.as_secs_f64());
+                        for warning in &report.warnings {
+                            self.install_status.push('\n');
+                            self.install_status.push_str(warning);
+                        }
+                    }
+                    Err(err) => {
+                        self.install_progress = InstallProgress::Fraction(0.0);
+                        self.install_status = failure_status(&err);
+
+                        return Message::Error(err).into();
+                    }
+                }
+            }
+            Message::FocusNext { backwards } => self.focus_next(backwards),
+            Message::MoveHighlight { down } => match self.selected_loader {
+                Loader::Fabric => self.fabric.move_highlight(down),
+                Loader::Forge => self.forge.move_highlight(down),
+                Loader::Quilt => self.quilt.move_highlight(down),
+            },
+            Message::CloseRequested => {
+                if self.install_running {
+                    let confirmed = MessageDialog::new()
+                        .set_type(MessageType::Warning)
+                        .set_title("anymc-installer")
+                        .set_text(tr("close_while_installing"))
+                        .show_confirm();
+
+                    match confirmed {
+                        Ok(true) => {}
+                        Ok(false) => return Command::none(),
+                        Err(err) => tracing::warn!(error = ?err, "Failed to show close dialog"),
+                    }
+
+                    // Dropping the subscription cancels the install, which then removes what it
+                    // left unfinished
+                    self.install_job = None;
+                }
+
+                self.exiting = true;
             }
-            Message::InstallDone(_result) => {}
         }
 
         Command::none()
@@ -241,60 +1416,347 @@ impl Application for State {
 
     #[rustfmt::skip]
     fn view(&mut self) -> iced::Element<'_, Self::Message> {
+        if self.show_release_notes {
+            return self.release_notes_view().map(Message::Interaction);
+        }
+
+        let release_notes_label = if self.update_available() { tr("update_available") } else { tr("whats_new") };
+
+        let install_blocker = self.install_blocker();
+        let java_warning = self.java_warning();
+        let install_press = match (self.install_running, install_blocker) {
+            (true, _) => Some(Interaction::InstallPrevent),
+            (false, Some(_)) => None,
+            (false, None) => Some(Interaction::Install),
+        };
+        // The reason takes the status line while blocked, it's what the user needs to act on
+        let progress = match self.install_progress {
+            InstallProgress::Fraction(fraction) => fraction,
+            InstallProgress::Indeterminate => {
+                // Fills and empties again, so the bar visibly moves while nothing can be measured
+                let phase = (self.install_pulse % 40) as f32 / 20.0;
+                if phase <= 1.0 { phase } else { 2.0 - phase }
+            }
+        };
+
+        // Averaged over the whole install, libraries only come in a few at a time
+        let speed = match self.install_started {
+            Some(started) if self.install_running && self.install_downloaded > 0 => {
+                let secs = started.elapsed().as_secs_f64().max(0.1);
+                Some(format!("{:.1} MB/s", self.install_downloaded as f64 / (1024.0 * 1024.0) / secs))
+            }
+            _ => None,
+        };
+
+        let install_status = match (self.install_running, install_blocker) {
+            (false, Some(blocker)) => blocker.to_string(),
+            _ => self.install_status.clone(),
+        };
+
+        let mut install_button = Button::new(
+            &mut self.install_button,
+            Text::new(tr("install"))
+                .horizontal_alignment(Horizontal::Center)
+                .width(Length::Fill),
+            )
+            .width(Length::Fill);
+        // Enter in the directory input installs too, so the window can be used from the keyboard
+        let submit = install_press.clone().unwrap_or(Interaction::InstallPrevent);
+        if let Some(press) = install_press {
+            install_button = install_button.on_press(press);
+        }
+
+        let top_row = Row::new()
+            .padding(5)
+            .spacing(15)
+            .push(Tabs::new(u8::from(self.selected_loader).into(), Interaction::SelectLoader)
+                .push(TabLabel::Text("Fabric".to_string()), Row::new())
+                .push(TabLabel::Text("Forge".to_string()), Row::new())
+                .push(TabLabel::Text("Quilt".to_string()), Row::new())
+                )
+            .push(Tabs::new(u8::from(self.selected_side).into(), Interaction::SelectSide)
+                .push(TabLabel::Text(tr("client").to_string()), Row::new())
+                .push(TabLabel::Text(tr("server").to_string()), Row::new()))
+            .push(Checkbox::new(self.settings.expert_mode, tr("expert_mode"), Interaction::ExpertMode));
+        // Simple mode never offers unstable versions, so there's nothing to toggle
+        let top_row = if self.settings.expert_mode {
+            top_row.push(Checkbox::new(self.settings.show_unstable, tr("show_unstable"), Interaction::ShowUnstable))
+        } else {
+            top_row
+        };
+
         let column = Column::new()
             .padding(5)
             .spacing(5)
-            .push(Row::new()
-                .padding(5)
-                .spacing(15)
-                .push(Tabs::new(u8::from(self.selected_loader).into(), Interaction::SelectLoader)
-                    .push(TabLabel::Text("Fabric".to_string()), Row::new())
-                    .push(TabLabel::Text("Forge".to_string()), Row::new())
-                    .push(TabLabel::Text("Quilt".to_string()), Row::new())
-                    )
-                .push(Tabs::new(u8::from(self.selected_side).into(), Interaction::SelectSide)
-                    .push(TabLabel::Text("Client".to_string()), Row::new())
-                    .push(TabLabel::Text("Server".to_string()), Row::new()))
+            .push(top_row
+                .push(Button::new(&mut self.release_notes_button, Text::new(release_notes_label)).on_press(Interaction::ToggleReleaseNotes))
+                .align_items(Alignment::Center)
                 )
             .push(match self.selected_loader {
-                Loader::Fabric => self.fabric.view().map(Interaction::Fabric),
-                Loader::Forge => self.forge.view().map(Interaction::Forge),
-                Loader::Quilt => self.quilt.view().map(Interaction::Quilt),
+                Loader::Fabric => self.fabric.view(self.settings.expert_mode).map(Interaction::Fabric),
+                Loader::Forge => self.forge.view(self.settings.expert_mode).map(Interaction::Forge),
+                Loader::Quilt => self.quilt.view(self.settings.expert_mode).map(Interaction::Quilt),
             })
-            .push(Rule::horizontal(5))
-            .push(Row::new()
-                .push(Text::new("Directory:").width(Length::Units(140)))
-                .push(TextInput::new(&mut self.install_location_input, "Install Location", self.install_location.to_str().unwrap(), |s| Interaction::ChangeLocation(PathBuf::from(s))).padding(5))
-                .push(Button::new(&mut self.install_location_browse, Text::new("Browse...")).on_press(Interaction::BrowseLocation))
+            .push(Rule::horizontal(5));
+
+        let location_row = Row::new()
+            .push(Text::new(tr("directory")).width(Length::Units(140)))
+            .push(TextInput::new(&mut self.install_location_input, tr("install_location"), self.install_location.to_str().unwrap(), |s| Interaction::ChangeLocation(PathBuf::from(s))).on_submit(submit).padding(5).style(style::Location(self.location_valid)));
+        let location_row = if self.settings.recent_dirs.is_empty() {
+            location_row
+        } else {
+            let recent = self.settings.recent_dirs.iter().cloned().map(RecentDir).collect::<Vec<_>>();
+
+            location_row.push(PickList::new(&mut self.recent_pick_list, recent, None, |dir| Interaction::ChangeLocation(dir.0)).placeholder(tr("recent")).width(Length::Units(90)))
+        };
+
+        let column = column.push(location_row
+            .push(Button::new(&mut self.install_location_browse, Text::new(tr("browse"))).on_press(Interaction::BrowseLocation))
+            .width(Length::Fill)
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .padding(5));
+
+        let column = if self.location_unknown_vars.is_empty() {
+            column
+        } else {
+            column.push(Row::new()
+                .push(Text::new(format!("{}: {}", tr("unknown_variables"), self.location_unknown_vars.join(", "))))
+                .padding([0, 5, 5, 150]))
+        };
+
+        let column = if self.existing_profiles.is_empty() {
+            column
+        } else {
+            let mut reinstall = Button::new(&mut self.reinstall_button, Text::new(tr("reinstall")));
+            if self.selected_profile.is_some() && !self.install_running {
+                reinstall = reinstall.on_press(Interaction::Reinstall);
+            }
+
+            column.push(Row::new()
+                .push(Text::new(tr("existing_profiles")).width(Length::Units(140)))
+                .push(PickList::new(&mut self.existing_pick_list, &self.existing_profiles[..], self.selected_profile.clone(), Interaction::SelectProfile).width(Length::Fill))
+                .push(reinstall)
                 .width(Length::Fill)
                 .align_items(Alignment::Center)
                 .spacing(5)
                 .padding(5))
+        };
+
+        let advanced_label = if self.settings.show_advanced { tr("hide_advanced") } else { tr("show_advanced") };
+        let advanced_button = Button::new(&mut self.advanced_button, Text::new(advanced_label)).on_press(Interaction::ToggleAdvanced);
+
+        let column = column
             .push(match self.selected_side {
                 Side::Client => Row::new()
-                    .push(Text::new("Options:").width(Length::Units(140)))
-                    .push(Checkbox::new(self.client_generate_profile, "Generate profile", Interaction::ClientGenerateProfile))
+                    .push(Text::new(tr("options")).width(Length::Units(140)))
+                    .push(Checkbox::new(self.client_generate_profile, tr("generate_profile"), Interaction::ClientGenerateProfile).width(Length::Fill))
+                    .push(advanced_button)
+                    .align_items(Alignment::Center)
                     .spacing(5)
                     .padding(5),
                 Side::Server => Row::new()
-                    .push(Text::new("Options:").width(Length::Units(140)))
-                    .push(Checkbox::new(self.server_download_jar, "Download server jar", Interaction::ServerDownloadJar))
-                    .push(Checkbox::new(self.server_generate_script, "Generate launch script", Interaction::ServerGenerateScript))
+                    .push(Text::new(tr("options")).width(Length::Units(140)))
+                    .push(Checkbox::new(self.server_download_jar, tr("download_server_jar"), Interaction::ServerDownloadJar))
+                    .push(Checkbox::new(self.server_accept_eula, tr("accept_eula"), Interaction::ServerAcceptEula).width(Length::Fill))
+                    .push(advanced_button)
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .padding(5),
+            });
+
+        // Options most installs never need to touch, hidden until asked for
+        let column = if !self.settings.show_advanced {
+            column
+        } else {
+            let mut copy_command = Button::new(&mut self.copy_command_button, Text::new(tr("copy_command")));
+            if install_blocker.is_none() {
+                copy_command = copy_command.on_press(Interaction::CopyCommand);
+            }
+
+            column.push(match self.selected_side {
+                Side::Client => Row::new()
+                    .push(Text::new(tr("advanced")).width(Length::Units(140)))
+                    .push(Checkbox::new(self.client_skip_placeholder_jar, tr("skip_placeholder_jar"), Interaction::ClientSkipPlaceholderJar))
+                    .push(Checkbox::new(self.client_keep_existing, tr("keep_existing"), Interaction::ClientKeepExisting).width(Length::Fill))
+                    .push(copy_command)
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .padding(5),
+                Side::Server => Row::new()
+                    .push(Text::new(tr("advanced")).width(Length::Units(140)))
+                    .push(Checkbox::new(self.server_generate_script, tr("generate_launch_script"), Interaction::ServerGenerateScript))
+                    .push(Checkbox::new(self.server_compress_jar, tr("compress_launch_jar"), Interaction::ServerCompressJar).width(Length::Fill))
+                    .push(copy_command)
+                    .align_items(Alignment::Center)
                     .spacing(5)
                     .padding(5),
             })
+        };
+
+        let column = if !self.settings.show_advanced {
+            column
+        } else {
+            let java = match &self.java {
+                None => tr("java_searching").to_string(),
+                Some(Ok(java)) => java.to_string(),
+                Some(Err(_)) => tr("java_not_found").to_string(),
+            };
+
+            column.push(Row::new()
+                .push(Text::new(tr("java")).width(Length::Units(140)))
+                .push(Text::new(java))
+                .align_items(Alignment::Center)
+                .spacing(5)
+                .padding(5))
+        };
+
+        let column = if !self.settings.show_advanced {
+            column
+        } else {
+            let name = self.preset_name.trim();
+
+            let mut save = Button::new(&mut self.save_preset_button, Text::new(tr("save")));
+            if !name.is_empty() {
+                save = save.on_press(Interaction::SavePreset);
+            }
+            let mut rename = Button::new(&mut self.rename_preset_button, Text::new(tr("rename")));
+            if !name.is_empty() && self.selected_preset.as_deref().map_or(false, |selected| selected != name) {
+                rename = rename.on_press(Interaction::RenamePreset);
+            }
+            let mut delete = Button::new(&mut self.delete_preset_button, Text::new(tr("delete")));
+            if self.selected_preset.is_some() {
+                delete = delete.on_press(Interaction::DeletePreset);
+            }
+
+            column.push(Row::new()
+                .push(Text::new(tr("presets")).width(Length::Units(140)))
+                .push(PickList::new(&mut self.preset_pick_list, self.presets.names(), self.selected_preset.clone(), Interaction::SelectPreset).width(Length::Fill))
+                .push(TextInput::new(&mut self.preset_name_input, tr("preset_name"), &self.preset_name, Interaction::PresetNameChanged).padding(5).width(Length::Units(110)))
+                .push(save)
+                .push(rename)
+                .push(delete)
+                .align_items(Alignment::Center)
+                .spacing(5)
+                .padding(5))
+        };
+
+        let column = match java_warning {
+            Some(warning) => column.push(Row::new().push(Text::new(warning)).padding(5)),
+            None => column,
+        };
+
+        let column = column
             .push(Rule::horizontal(5))
-            .push(Button::new(
-                &mut self.install_button,
-                Text::new("Install")
-                    .horizontal_alignment(Horizontal::Center)
-                    .width(Length::Fill),
-                )
-                .width(Length::Fill)
-                .on_press(if self.install_running { Interaction::InstallPrevent } else { Interaction::Install }))
-            .push(ProgressBar::new(0.0..=1.0, self.install_progress));
+            .push(install_button)
+            .push(match speed {
+                Some(speed) => Row::new()
+                    .push(ProgressBar::new(0.0..=1.0, progress))
+                    .push(Text::new(speed).width(Length::Units(80)).horizontal_alignment(Horizontal::Right))
+                    .align_items(Alignment::Center)
+                    .spacing(5),
+                None => Row::new().push(ProgressBar::new(0.0..=1.0, progress)),
+            })
+            .push(Text::new(install_status));
 
         let content: Element<Interaction> = column.into();
         content.map(Message::Interaction)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_tab_in_order() {
+        assert_eq!(from_tab::<Loader>(0), Some(Loader::Fabric));
+        assert_eq!(from_tab::<Loader>(2), Some(Loader::Quilt));
+        assert_eq!(from_tab::<Side>(1), Some(Side::Server));
+    }
+
+    #[test]
+    fn unknown_tab_is_ignored() {
+        let mut state = State {
+            selected_loader: Loader::Fabric,
+            selected_side: Side::Server,
+            ..State::default()
+        };
+
+        let _ = state.update(Message::Interaction(Interaction::SelectLoader(3)));
+        let _ = state.update(Message::Interaction(Interaction::SelectSide(2)));
+
+        assert_eq!(state.selected_loader, Loader::Fabric);
+        assert_eq!(state.selected_side, Side::Server);
+    }
+
+    fn fabric_selected(flavor: fabric::Flavor) -> State {
+        let mut state = State {
+            selected_loader: Loader::Fabric,
+            ..State::default()
+        };
+        state.fabric.flavor = flavor;
+        state.fabric.selected_version = Some(fabric::Version {
+            separator: ".".to_string(),
+            build: 1,
+            maven: "net.fabricmc:fabric-loader:0.14.10".to_string(),
+            version: "0.14.10".to_string(),
+            stable: true,
+            recommended: false,
+        });
+        state.fabric.minecraft.selected_version = Some(minecraft::Version {
+            version: "1.8.9".to_string(),
+            stable: true,
+            kind: None,
+        });
+
+        state
+    }
+
+    #[test]
+    fn cli_command_points_legacy_fabric_at_its_services() {
+        let command = fabric_selected(fabric::Flavor::LegacyFabric)
+            .cli_command()
+            .unwrap();
+
+        assert!(command.contains(&format!("--fabric-meta-url {}", fabric::LEGACY_META)));
+        assert!(command.contains(&format!("--maven-mirror {}", fabric::LEGACY_MAVEN)));
+    }
+
+    #[test]
+    fn cli_command_leaves_fabric_on_defaults() {
+        let command = fabric_selected(fabric::Flavor::Fabric)
+            .cli_command()
+            .unwrap();
+
+        assert!(!command.contains("--fabric-meta-url"));
+        assert!(!command.contains("--maven-mirror"));
+    }
+
+    #[test]
+    fn focus_follows_layout() {
+        let mut state = State {
+            selected_loader: Loader::Quilt,
+            ..State::default()
+        };
+        state.settings.expert_mode = true;
+        state.settings.show_advanced = false;
+
+        state.focus_next(false);
+        assert!(state.quilt.minecraft.autocomplete.is_focused());
+
+        state.focus_next(false);
+        assert!(!state.quilt.minecraft.autocomplete.is_focused());
+        assert!(state.quilt.autocomplete.is_focused());
+
+        state.focus_next(false);
+        assert!(!state.quilt.autocomplete.is_focused());
+        assert!(state.install_location_input.is_focused());
+
+        // Wraps around, and back again
+        state.focus_next(false);
+        assert!(state.quilt.minecraft.autocomplete.is_focused());
+        state.focus_next(true);
+        assert!(state.install_location_input.is_focused());
+    }
+}
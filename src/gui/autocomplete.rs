@@ -0,0 +1,160 @@
+use std::fmt::Display;
+
+use iced::{button, text_input, Button, Column, Element, Length, Row, Text, TextInput};
+
+use crate::{gui::style, utils::i18n::tr};
+
+/// How many matches are listed below the input at most.
+const MAX_MATCHES: usize = 5;
+
+/// A text input listing the versions matching what's typed below it, picked with the arrow keys
+/// and Enter or by clicking one.
+///
+/// Only the typed text and highlight live here, the owner passes in the versions it currently
+/// offers so the matches always agree with its pick list.
+#[derive(Debug, Default)]
+pub struct Autocomplete {
+    input: text_input::State,
+    query: String,
+    highlighted: usize,
+    buttons: Vec<button::State>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Changed(String),
+    /// Enter was pressed, picks the highlighted match.
+    Submit,
+    /// A match was clicked, by its position in the list.
+    Pick(usize),
+}
+
+impl Autocomplete {
+    pub fn is_focused(&self) -> bool {
+        self.input.is_focused()
+    }
+
+    /// The text input, for moving the keyboard focus to it.
+    pub fn input(&mut self) -> &mut text_input::State {
+        &mut self.input
+    }
+
+    /// The versions containing the typed text ignoring case, in the order they're offered in.
+    fn matches<'a, T: Display>(&self, versions: &'a [T]) -> Vec<&'a T> {
+        let query = self.query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        versions
+            .iter()
+            .filter(|version| version.to_string().to_lowercase().contains(&query))
+            .take(MAX_MATCHES)
+            .collect()
+    }
+
+    /// Returns the version that was picked, if any.
+    ///
+    /// Submitting text nothing matches picks nothing, so the previous selection stays.
+    pub fn update<T: Display + Clone>(&mut self, event: Event, versions: &[T]) -> Option<T> {
+        let index = match event {
+            Event::Changed(query) => {
+                self.query = query;
+                self.highlighted = 0;
+
+                return None;
+            }
+            Event::Submit => self.highlighted,
+            Event::Pick(index) => index,
+        };
+
+        let picked = self
+            .matches(versions)
+            .get(index)
+            .map(|&version| version.clone());
+        if picked.is_some() {
+            self.query.clear();
+            self.highlighted = 0;
+        }
+
+        picked
+    }
+
+    /// Moves the highlight through the matches, only while the input has the keyboard focus.
+    pub fn move_highlight<T: Display>(&mut self, down: bool, versions: &[T]) {
+        let count = self.matches(versions).len();
+        if !self.is_focused() || count == 0 {
+            return;
+        }
+
+        self.highlighted = if down {
+            (self.highlighted + 1) % count
+        } else {
+            (self.highlighted + count - 1) % count
+        };
+    }
+
+    /// Returns the input and, while something is typed, the matches to put below its row.
+    pub fn view<'a, T: Display, M: Clone + 'a>(
+        &'a mut self,
+        versions: &[T],
+        on_event: fn(Event) -> M,
+    ) -> (Element<'a, M>, Option<Element<'a, M>>) {
+        let matches = self
+            .matches(versions)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        let Self {
+            input,
+            query,
+            highlighted,
+            buttons,
+        } = self;
+
+        let typing = !query.trim().is_empty();
+        let input = TextInput::new(input, tr("type_version"), query, move |query| {
+            on_event(Event::Changed(query))
+        })
+        .on_submit(on_event(Event::Submit))
+        .width(Length::Units(110))
+        .padding(5)
+        .into();
+
+        if !typing {
+            return (input, None);
+        }
+        if matches.is_empty() {
+            return (input, Some(Text::new(tr("no_match")).into()));
+        }
+
+        buttons.resize_with(matches.len(), Default::default);
+        let list = matches
+            .into_iter()
+            .zip(buttons.iter_mut())
+            .enumerate()
+            .fold(
+                Column::new().width(Length::Fill),
+                |list, (index, (label, state))| {
+                    list.push(
+                        Button::new(state, Text::new(label))
+                            .width(Length::Fill)
+                            .on_press(on_event(Event::Pick(index)))
+                            .style(style::Suggestion(index == *highlighted)),
+                    )
+                },
+            );
+
+        (input, Some(list.into()))
+    }
+}
+
+/// Lines the matches up below the input, for rows starting with the usual 140 wide label.
+pub fn indent<'a, M: 'a>(matches: Element<'a, M>) -> Element<'a, M> {
+    Row::new()
+        .push(matches)
+        .width(Length::Fill)
+        .padding([0, 5, 5, 150])
+        .into()
+}
@@ -0,0 +1,60 @@
+use iced::{button, text_input, Background, Color};
+
+/// Colors the directory input's border by whether the location can be installed into, `None`
+/// while it hasn't been checked yet.
+pub struct Location(pub Option<bool>);
+
+impl Location {
+    fn border(&self, neutral: Color) -> Color {
+        match self.0 {
+            Some(true) => Color::from_rgb(0.2, 0.6, 0.2),
+            Some(false) => Color::from_rgb(0.8, 0.2, 0.2),
+            None => neutral,
+        }
+    }
+}
+
+impl text_input::StyleSheet for Location {
+    fn active(&self) -> text_input::Style {
+        text_input::Style {
+            background: Background::Color(Color::WHITE),
+            border_radius: 5.0,
+            border_width: 1.0,
+            border_color: self.border(Color::from_rgb(0.7, 0.7, 0.7)),
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        text_input::Style {
+            border_color: self.border(Color::from_rgb(0.5, 0.5, 0.5)),
+            ..self.active()
+        }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        Color::from_rgb(0.7, 0.7, 0.7)
+    }
+
+    fn value_color(&self) -> Color {
+        Color::from_rgb(0.3, 0.3, 0.3)
+    }
+
+    fn selection_color(&self) -> Color {
+        Color::from_rgb(0.8, 0.8, 1.0)
+    }
+}
+
+/// A match listed below an autocomplete input, highlighted if Enter picks it.
+pub struct Suggestion(pub bool);
+
+impl button::StyleSheet for Suggestion {
+    fn active(&self) -> button::Style {
+        button::Style {
+            background: self
+                .0
+                .then(|| Background::Color(Color::from_rgb(0.8, 0.8, 1.0))),
+            text_color: Color::from_rgb(0.3, 0.3, 0.3),
+            ..button::Style::default()
+        }
+    }
+}
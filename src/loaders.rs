@@ -1,12 +1,31 @@
 pub mod fabric;
 pub mod forge;
+pub mod maven;
 pub mod minecraft;
 pub mod quilt;
+pub mod urls;
 
-use std::path::PathBuf;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use futures::Stream;
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use crate::{lockfile::Lockfile, utils};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-#[derive(num_enum::IntoPrimitive, num_enum::FromPrimitive)]
+#[derive(num_enum::IntoPrimitive, clap::ValueEnum)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum Side {
     #[default]
@@ -15,7 +34,9 @@ pub enum Side {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-#[derive(num_enum::IntoPrimitive, num_enum::FromPrimitive)]
+#[derive(num_enum::IntoPrimitive, clap::ValueEnum)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum Loader {
     Fabric,
@@ -24,10 +45,50 @@ pub enum Loader {
     Quilt,
 }
 
+/// How the manifest only server launch jar is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(clap::ValueEnum)]
+pub enum JarCompression {
+    /// There's nothing worth compressing in a lone manifest, and some minimal JVMs prefer it.
+    #[default]
+    Stored,
+    Deflated,
+}
+
+impl From<JarCompression> for zip::CompressionMethod {
+    fn from(compression: JarCompression) -> Self {
+        match compression {
+            JarCompression::Stored => zip::CompressionMethod::Stored,
+            JarCompression::Deflated => zip::CompressionMethod::Deflated,
+        }
+    }
+}
+
+/// How the launcher json files an install writes are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(clap::ValueEnum)]
+pub enum JsonFormat {
+    /// Indented like the launcher's own files, keeping diffs of a tracked `.minecraft` small.
+    #[default]
+    Pretty,
+    Compact,
+}
+
+impl JsonFormat {
+    pub fn serialize<T: serde::Serialize>(self, value: &T) -> serde_json::Result<String> {
+        match self {
+            JsonFormat::Pretty => serde_json::to_string_pretty(value),
+            JsonFormat::Compact => serde_json::to_string(value),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LoaderVersion {
     Fabric(fabric::Version),
-    Forge(bool),
+    Forge(forge::Version),
     Quilt(quilt::Version),
 }
 
@@ -55,47 +116,863 @@ impl LoaderVersion {
             Loader::Quilt => crate::QUILT_ICON,
         }
     }
+
+    /// Returns the launcher profile icon, a custom PNG if one is given or the baked in one.
+    ///
+    /// Both are checked to decode, a broken icon would only go unshown in the launcher.
+    pub fn icon_bytes(&self, custom: Option<&Path>) -> anyhow::Result<Cow<'static, [u8]>> {
+        let icon = match custom {
+            Some(path) => Cow::Owned(
+                std::fs::read(path)
+                    .with_context(|| format!("Failed to read custom icon: {}", path.display()))?,
+            ),
+            None => Cow::Borrowed(self.icon()),
+        };
+
+        match utils::fit_icon(&icon) {
+            Ok(Some(scaled)) => Ok(Cow::Owned(scaled)),
+            Ok(None) => Ok(icon),
+            Err(err) => Err(err.context(match custom {
+                Some(path) => format!("Custom icon is not a valid PNG: {}", path.display()),
+                None => format!("Built-in {:?} icon is not a valid PNG", self.loader()),
+            })),
+        }
+    }
 }
 
 impl std::fmt::Display for LoaderVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            LoaderVersion::Fabric(version) => version.fmt(f),
-            LoaderVersion::Forge(version) => version.fmt(f),
-            LoaderVersion::Quilt(version) => version.fmt(f),
+            LoaderVersion::Fabric(version) => version.version.fmt(f),
+            LoaderVersion::Forge(version) => version.version.fmt(f),
+            LoaderVersion::Quilt(version) => version.version.fmt(f),
+        }
+    }
+}
+
+/// A client install found in `versions/`, recognised by the `{loader}-{version}-{minecraft}`
+/// names Fabric and Quilt client installs get.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExistingProfile {
+    pub name: String,
+    pub loader: Loader,
+    pub version: String,
+    pub minecraft: String,
+}
+
+impl ExistingProfile {
+    /// Splits a version directory name back into its parts, `None` if this installer didn't
+    /// create it.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (loader, rest) = if let Some(rest) = name.strip_prefix("fabric-loader-") {
+            (Loader::Fabric, rest)
+        } else if let Some(rest) = name.strip_prefix("quilt-loader-") {
+            (Loader::Quilt, rest)
+        } else {
+            return None;
+        };
+
+        // Both halves can contain dashes, but only loader versions have suffixes like `-beta.1`
+        // while Minecraft versions always start with a digit
+        let (version, minecraft) = rest
+            .match_indices('-')
+            .map(|(i, _)| (&rest[..i], &rest[i + 1..]))
+            .find(|(version, minecraft)| {
+                !version.is_empty() && minecraft.starts_with(|c: char| c.is_ascii_digit())
+            })?;
+
+        Some(Self {
+            name: name.to_string(),
+            loader,
+            version: version.to_string(),
+            minecraft: minecraft.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for ExistingProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.name.fmt(f)
+    }
+}
+
+/// Finds the newest earlier install of the same loader and Minecraft version if installing
+/// `version` would be a downgrade from it.
+pub fn newer_installed<'a>(
+    profiles: &'a [ExistingProfile],
+    version: &LoaderVersion,
+    minecraft: &str,
+) -> Option<&'a ExistingProfile> {
+    let installing = version.to_string();
+
+    profiles
+        .iter()
+        .filter(|p| p.loader == version.loader() && p.minecraft == minecraft)
+        .max_by(|a, b| utils::compare_versions(&a.version, &b.version))
+        .filter(|p| utils::compare_versions(&p.version, &installing) == Ordering::Greater)
+}
+
+/// Lists the installs in `dir/versions` this installer made, ignoring anything else in there.
+pub async fn existing_profiles(dir: PathBuf) -> anyhow::Result<Vec<ExistingProfile>> {
+    let versions_dir = dir.join("versions");
+    if !versions_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut profiles = Vec::new();
+
+    let mut entries = tokio::fs::read_dir(&versions_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+
+        if let Some(profile) = entry.file_name().to_str().and_then(ExistingProfile::parse) {
+            profiles.push(profile);
+        }
+    }
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(profiles)
+}
+
+/// How far along an install is, sent to the GUI so the progress bar moves for both sides.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub step: String,
+    /// Between `0.0` and `1.0`, `None` while there's no telling how far along the step is.
+    pub fraction: Option<f32>,
+    /// Bytes the install downloaded so far, libraries found on disk or in a cache don't count.
+    pub downloaded: u64,
+}
+
+/// Hands install progress to whoever is watching, does nothing for headless installs.
+///
+/// Clones share the download count, a new reporter for every install starts it over.
+#[derive(Debug, Clone, Default)]
+pub struct Reporter {
+    sender: Option<UnboundedSender<Progress>>,
+    downloaded: Arc<AtomicU64>,
+}
+
+impl Reporter {
+    pub fn new(sender: UnboundedSender<Progress>) -> Self {
+        Self {
+            sender: Some(sender),
+            downloaded: Arc::default(),
+        }
+    }
+
+    /// Counts downloaded bytes, they're passed along with the next report.
+    pub fn add_downloaded(&self, bytes: u64) {
+        self.downloaded.fetch_add(bytes, AtomicOrdering::Relaxed);
+    }
+
+    pub fn report(&self, step: impl Into<String>, fraction: f32) {
+        self.send(step.into(), Some(fraction));
+    }
+
+    /// Reports a step that can't be measured, like waiting on a single request.
+    pub fn report_waiting(&self, step: impl Into<String>) {
+        self.send(step.into(), None);
+    }
+
+    fn send(&self, step: String, fraction: Option<f32>) {
+        if let Some(sender) = &self.sender {
+            let downloaded = self.downloaded.load(AtomicOrdering::Relaxed);
+
+            // The receiver going away only means nobody is watching anymore
+            let _ = sender.send(Progress {
+                step,
+                fraction,
+                downloaded,
+            });
+        }
+    }
+}
+
+/// What an install produced, for callers that want more than whether it worked.
+#[derive(Debug, Clone, Default)]
+#[derive(serde::Serialize)]
+pub struct InstallReport {
+    /// The version the launcher knows the install by, clients only.
+    pub profile_name: Option<String>,
+    /// The same version was installed already, so its files were left as they were.
+    pub up_to_date: bool,
+    /// Everything written other than libraries and the launch jar.
+    pub files: Vec<PathBuf>,
+    /// Maven names of the downloaded libraries and where they were put, servers only.
+    pub libraries: Vec<(String, PathBuf)>,
+    pub launch_jar: Option<PathBuf>,
+    /// Things the install couldn't fix itself that may keep it from starting.
+    pub warnings: Vec<String>,
+    /// Wall-clock time the whole install took, written out in seconds.
+    #[serde(serialize_with = "serialize_secs")]
+    pub elapsed: Duration,
+}
+
+fn serialize_secs<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Roughly what an install failed on, so the user can be pointed at the likely fix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A request failed or a server answered with an error.
+    Network,
+    /// Reading or writing in the install directory failed.
+    Filesystem,
+    /// A download didn't match the hash it was pinned to.
+    Checksum,
+    Other,
+}
+
+impl ErrorKind {
+    /// Classifies an error by the first cause in its chain that says anything about it.
+    pub fn of(err: &anyhow::Error) -> Self {
+        for cause in err.chain() {
+            if cause.is::<crate::lockfile::ChecksumMismatch>() {
+                return ErrorKind::Checksum;
+            }
+            if cause.is::<reqwest::Error>() {
+                return ErrorKind::Network;
+            }
+            if cause.is::<std::io::Error>() {
+                return ErrorKind::Filesystem;
+            }
+        }
+
+        ErrorKind::Other
+    }
+
+    /// Translation key of a short hint on what to try next.
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            ErrorKind::Network => Some("hint_network"),
+            ErrorKind::Filesystem => Some("hint_filesystem"),
+            ErrorKind::Checksum => Some("hint_checksum"),
+            ErrorKind::Other => None,
         }
     }
 }
 
+/// How much an install is going to download, for deciding whether to go ahead on a metered
+/// connection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DownloadEstimate {
+    pub bytes: u64,
+    /// Some server didn't say how large a file is, so the real total is higher.
+    pub approximate: bool,
+}
+
+impl std::fmt::Display for DownloadEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let megabytes = self.bytes as f64 / (1024.0 * 1024.0);
+
+        if self.approximate {
+            write!(f, "at least {:.1} MB", megabytes)
+        } else {
+            write!(f, "{:.1} MB", megabytes)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Install<V> {
     pub version: V,
     pub side: Side,
     pub dir: PathBuf,
     pub minecraft: minecraft::Version,
-    pub generate: bool,
+    /// Add a profile for the install to the vanilla launcher, clients only.
+    pub generate_profile: bool,
+    /// Write the empty version jar the vanilla launcher expects, clients only.
+    pub placeholder_jar: bool,
+    /// Copy the vanilla client jar in place of the empty placeholder, for launchers that refuse to
+    /// start a version with an empty jar, clients only.
+    pub vanilla_jar: bool,
+    /// Install next to an existing version of the same name instead of replacing it, clients
+    /// only.
+    pub keep_existing: bool,
+    /// Layout of the profile json and `launcher_profiles.json`, clients only.
+    pub json_format: JsonFormat,
+    /// Write start scripts next to the launch jar, servers only.
+    pub generate_script: bool,
+    /// Write a `Dockerfile` and `docker-compose.yml` that run the launch jar, servers only.
+    pub docker: bool,
+    /// Check that Java can load the launch jar's main class, servers only.
+    pub smoke_test: bool,
+    /// The Java runtime to run installers with and write into start scripts. Found when not set,
+    /// the scripts then leave it to the `PATH` as the found one only exists on this machine.
+    pub java: Option<PathBuf>,
+    /// The install is run somewhere else, like `--output-zip` ones, so the Java found here isn't
+    /// checked against the version, servers only.
+    pub portable: bool,
+    /// Keep the `org.quiltmc:hashed` libraries Quilt installs normally drop, only for debugging.
+    pub keep_hashed_libraries: bool,
+    /// Compression of the launch jar, servers only.
+    pub jar_compression: JarCompression,
+    /// Download the vanilla server jar, servers only.
+    pub download_jar: bool,
+    /// Agree to the Minecraft EULA in `eula.txt`, servers only.
+    pub accept_eula: bool,
+    pub icon: Option<PathBuf>,
+    /// JVM arguments the launcher profile starts the game with, clients only.
+    pub jvm_args: Option<String>,
+    /// Replace a launcher profile of the same name instead of only updating the version and
+    /// icon on it, clients only.
+    pub replace_profile: bool,
+    /// A `libraries` directory to copy already downloaded libraries from.
+    pub library_cache: Option<PathBuf>,
+    /// Install exactly what a lockfile recorded, failing if a library changed since.
+    pub locked: Option<Lockfile>,
+    /// Jars added to the end of the launch jar's Class-Path, servers only.
+    pub extra_libs: Vec<PathBuf>,
+    /// Cap the combined speed of library downloads, in KB/s.
+    pub max_bandwidth: Option<u64>,
+    /// Check every library can be downloaded before downloading any, servers only.
+    pub preflight: bool,
+    /// Where libraries are downloaded from, servers only.
+    pub mirrors: maven::Mirrors,
+    pub urls: urls::BaseUrls,
+    pub progress: Reporter,
+    /// Files that aren't complete yet, removed if the install is aborted.
+    pub cleanup: utils::cleanup::Cleanup,
+}
+
+impl<V> Install<V> {
+    /// Swaps out the version, used to hand a loader its own version type.
+    pub fn with_version<T>(self, version: T) -> Install<T> {
+        Install {
+            version,
+            side: self.side,
+            dir: self.dir,
+            minecraft: self.minecraft,
+            generate_profile: self.generate_profile,
+            placeholder_jar: self.placeholder_jar,
+            vanilla_jar: self.vanilla_jar,
+            keep_existing: self.keep_existing,
+            json_format: self.json_format,
+            generate_script: self.generate_script,
+            docker: self.docker,
+            smoke_test: self.smoke_test,
+            java: self.java,
+            portable: self.portable,
+            keep_hashed_libraries: self.keep_hashed_libraries,
+            jar_compression: self.jar_compression,
+            download_jar: self.download_jar,
+            accept_eula: self.accept_eula,
+            icon: self.icon,
+            jvm_args: self.jvm_args,
+            replace_profile: self.replace_profile,
+            library_cache: self.library_cache,
+            locked: self.locked,
+            extra_libs: self.extra_libs,
+            max_bandwidth: self.max_bandwidth,
+            preflight: self.preflight,
+            mirrors: self.mirrors,
+            urls: self.urls,
+            progress: self.progress,
+            cleanup: self.cleanup,
+        }
+    }
+}
+
+impl Install<LoaderVersion> {
+    pub fn builder() -> InstallBuilder {
+        InstallBuilder::default()
+    }
+}
+
+/// Builds an [`Install`], everything but what to install and where has a sensible default.
+pub struct InstallBuilder {
+    version: Option<LoaderVersion>,
+    side: Side,
+    dir: Option<PathBuf>,
+    minecraft: Option<minecraft::Version>,
+    generate_profile: bool,
+    placeholder_jar: bool,
+    vanilla_jar: bool,
+    keep_existing: bool,
+    json_format: JsonFormat,
+    generate_script: bool,
+    docker: bool,
+    smoke_test: bool,
+    java: Option<PathBuf>,
+    portable: bool,
+    keep_hashed_libraries: bool,
+    jar_compression: JarCompression,
+    download_jar: bool,
+    accept_eula: bool,
+    icon: Option<PathBuf>,
+    jvm_args: Option<String>,
+    replace_profile: bool,
+    library_cache: Option<PathBuf>,
+    locked: Option<Lockfile>,
+    extra_libs: Vec<PathBuf>,
+    max_bandwidth: Option<u64>,
+    preflight: bool,
+    mirrors: Option<maven::Mirrors>,
+    urls: urls::BaseUrls,
+    progress: Reporter,
+    cleanup: utils::cleanup::Cleanup,
+}
+
+impl Default for InstallBuilder {
+    fn default() -> Self {
+        Self {
+            version: None,
+            side: Side::default(),
+            dir: None,
+            minecraft: None,
+            generate_profile: true,
+            placeholder_jar: true,
+            vanilla_jar: false,
+            keep_existing: false,
+            json_format: JsonFormat::default(),
+            generate_script: false,
+            docker: false,
+            smoke_test: false,
+            java: None,
+            portable: false,
+            keep_hashed_libraries: false,
+            jar_compression: JarCompression::default(),
+            download_jar: false,
+            accept_eula: false,
+            icon: None,
+            jvm_args: None,
+            replace_profile: false,
+            library_cache: None,
+            locked: None,
+            extra_libs: Vec::new(),
+            max_bandwidth: None,
+            preflight: true,
+            mirrors: None,
+            urls: urls::BaseUrls::default(),
+            progress: Reporter::default(),
+            cleanup: utils::cleanup::Cleanup::default(),
+        }
+    }
+}
+
+impl InstallBuilder {
+    pub fn version(mut self, version: LoaderVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    pub fn minecraft(mut self, minecraft: minecraft::Version) -> Self {
+        self.minecraft = Some(minecraft);
+        self
+    }
+
+    pub fn generate_profile(mut self, generate: bool) -> Self {
+        self.generate_profile = generate;
+        self
+    }
+
+    pub fn placeholder_jar(mut self, placeholder: bool) -> Self {
+        self.placeholder_jar = placeholder;
+        self
+    }
+
+    pub fn vanilla_jar(mut self, vanilla: bool) -> Self {
+        self.vanilla_jar = vanilla;
+        self
+    }
+
+    pub fn keep_existing(mut self, keep: bool) -> Self {
+        self.keep_existing = keep;
+        self
+    }
+
+    pub fn json_format(mut self, format: JsonFormat) -> Self {
+        self.json_format = format;
+        self
+    }
+
+    pub fn generate_script(mut self, generate: bool) -> Self {
+        self.generate_script = generate;
+        self
+    }
+
+    pub fn docker(mut self, docker: bool) -> Self {
+        self.docker = docker;
+        self
+    }
+
+    pub fn smoke_test(mut self, smoke_test: bool) -> Self {
+        self.smoke_test = smoke_test;
+        self
+    }
+
+    pub fn java(mut self, java: Option<PathBuf>) -> Self {
+        self.java = java;
+        self
+    }
+
+    pub fn portable(mut self, portable: bool) -> Self {
+        self.portable = portable;
+        self
+    }
+
+    pub fn keep_hashed_libraries(mut self, keep_hashed_libraries: bool) -> Self {
+        self.keep_hashed_libraries = keep_hashed_libraries;
+        self
+    }
+
+    pub fn jar_compression(mut self, compression: JarCompression) -> Self {
+        self.jar_compression = compression;
+        self
+    }
+
+    pub fn download_jar(mut self, download: bool) -> Self {
+        self.download_jar = download;
+        self
+    }
+
+    pub fn accept_eula(mut self, accept: bool) -> Self {
+        self.accept_eula = accept;
+        self
+    }
+
+    pub fn icon(mut self, icon: Option<PathBuf>) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    pub fn jvm_args(mut self, args: Option<String>) -> Self {
+        self.jvm_args = args;
+        self
+    }
+
+    pub fn replace_profile(mut self, replace: bool) -> Self {
+        self.replace_profile = replace;
+        self
+    }
+
+    pub fn library_cache(mut self, cache: Option<PathBuf>) -> Self {
+        self.library_cache = cache;
+        self
+    }
+
+    pub fn locked(mut self, lock: Option<Lockfile>) -> Self {
+        self.locked = lock;
+        self
+    }
+
+    pub fn extra_libs(mut self, libs: Vec<PathBuf>) -> Self {
+        self.extra_libs = libs;
+        self
+    }
+
+    pub fn max_bandwidth(mut self, kbps: Option<u64>) -> Self {
+        self.max_bandwidth = kbps;
+        self
+    }
+
+    pub fn preflight(mut self, check: bool) -> Self {
+        self.preflight = check;
+        self
+    }
+
+    /// Defaults to the official repositories of the base URLs.
+    pub fn mirrors(mut self, mirrors: maven::Mirrors) -> Self {
+        self.mirrors = Some(mirrors);
+        self
+    }
+
+    pub fn urls(mut self, urls: urls::BaseUrls) -> Self {
+        self.urls = urls;
+        self
+    }
+
+    pub fn progress(mut self, progress: Reporter) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn cleanup(mut self, cleanup: utils::cleanup::Cleanup) -> Self {
+        self.cleanup = cleanup;
+        self
+    }
+
+    /// Checks the options fit together, options for the other side are an error rather than
+    /// silently ignored.
+    pub fn build(self) -> anyhow::Result<Install<LoaderVersion>> {
+        let version = self.version.context("No loader version to install")?;
+        let dir = self.dir.context("No install directory")?;
+        let minecraft = self.minecraft.context("No Minecraft version to install")?;
+
+        if let Some(lock) = &self.locked {
+            if lock.side != self.side {
+                anyhow::bail!(
+                    "The lockfile is for a {:?} install, not {:?}",
+                    lock.side,
+                    self.side
+                );
+            }
+        }
+
+        if self.side == Side::Client && !self.extra_libs.is_empty() {
+            anyhow::bail!("Extra libraries are only supported for server installs");
+        }
+
+        let mirrors = self
+            .mirrors
+            .unwrap_or_else(|| maven::Mirrors::new(&self.urls));
+
+        Ok(Install {
+            version,
+            side: self.side,
+            dir,
+            minecraft,
+            generate_profile: self.generate_profile,
+            placeholder_jar: self.placeholder_jar,
+            vanilla_jar: self.vanilla_jar,
+            keep_existing: self.keep_existing,
+            json_format: self.json_format,
+            generate_script: self.generate_script,
+            docker: self.docker,
+            smoke_test: self.smoke_test,
+            java: self.java,
+            portable: self.portable,
+            keep_hashed_libraries: self.keep_hashed_libraries,
+            jar_compression: self.jar_compression,
+            download_jar: self.download_jar,
+            accept_eula: self.accept_eula,
+            icon: self.icon,
+            jvm_args: self.jvm_args,
+            replace_profile: self.replace_profile,
+            library_cache: self.library_cache,
+            locked: self.locked,
+            extra_libs: self.extra_libs,
+            max_bandwidth: self.max_bandwidth,
+            preflight: self.preflight,
+            mirrors,
+            urls: self.urls,
+            progress: self.progress,
+            cleanup: self.cleanup,
+        })
+    }
+}
+
+/// What a running install reports, any number of progress updates and then how it went.
+#[derive(Debug)]
+pub enum InstallEvent {
+    Progress(Progress),
+    Finished(anyhow::Result<InstallReport>),
 }
 
-#[rustfmt::skip]
-pub async fn install(
-    loader: LoaderVersion,
+/// Runs the install as the stream is polled, ending after its `Finished` event.
+///
+/// Progress goes to the stream instead of the install's own reporter, and dropping the stream
+/// drops the install with it, removing what it left unfinished.
+pub fn install_events(mut install: Install<LoaderVersion>) -> impl Stream<Item = InstallEvent> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    install.progress = Reporter::new(sender);
+
+    let cleanup = install.cleanup.clone();
+    let running = Box::pin(async move {
+        // Only armed once polled, streams that are built but never run have nothing to clean up.
+        // Declared first so it's dropped after the install, once nothing writes anymore
+        let guard = cleanup.guard();
+        let result = self::install(install).await;
+        guard.disarm();
+
+        result
+    });
+    futures::stream::unfold(Some((running, receiver)), |state| async move {
+        let (mut running, mut receiver) = state?;
+
+        tokio::select! {
+            // Whatever was reported right before the install finished still goes out first
+            biased;
+            Some(progress) = receiver.recv() => {
+                Some((InstallEvent::Progress(progress), Some((running, receiver))))
+            }
+            result = &mut running => Some((InstallEvent::Finished(result), None)),
+        }
+    })
+}
+
+pub async fn install(mut install: Install<LoaderVersion>) -> anyhow::Result<InstallReport> {
+    let started = Instant::now();
+
+    // Servers are usually installed into a fresh folder, so only clients need an existing one
+    if install.side == Side::Server {
+        tokio::fs::create_dir_all(&install.dir).await?;
+    }
+
+    if !install.dir.exists() {
+        anyhow::bail!(
+            "Installation directory doesn't exist: {}",
+            install.dir.display()
+        );
+    }
+
+    // Resolve a symlinked directory so every path below it points at the real location
+    let metadata = tokio::fs::symlink_metadata(&install.dir).await?;
+    if metadata.file_type().is_symlink() {
+        install.dir = tokio::fs::canonicalize(&install.dir).await?;
+    }
+
+    // Fail before anything is deleted rather than half way through on a read-only mount
+    utils::check_writable(&install.dir).await?;
+
+    let versions_dir = install.dir.join("versions");
+    if install.side == Side::Client && versions_dir.exists() {
+        utils::check_writable(&versions_dir).await?;
+    }
+
+    let side = install.side;
+    let dir = install.dir.clone();
+    let accept_eula = install.accept_eula;
+    let progress = install.progress.clone();
+
+    // Installs like this behave differently from what users get, which has to show in bug reports
+    let keep_hashed = install.keep_hashed_libraries;
+    if keep_hashed {
+        tracing::warn!("Keeping the org.quiltmc:hashed libraries, remapping may silently fail");
+    }
+
+    // Servers run on this Java, one too old for the version is pointed out now rather than when
+    // the server fails to start
+    let mut java_warning = None;
+    if side == Side::Server && !install.portable {
+        match utils::java::find_or(install.java.as_deref()).await {
+            Ok(java) => {
+                let required =
+                    minecraft::fetch_java_version(&install.urls, &install.minecraft).await;
+                if !java.supports(required) {
+                    java_warning = Some(format!(
+                        "{} is too old for Minecraft {}, it needs Java {} or newer",
+                        java, install.minecraft, required
+                    ));
+                }
+            }
+            // A runtime that was asked for has to work, a missing one only matters once it's run
+            Err(err) if install.java.is_some() => return Err(err),
+            Err(err) => tracing::info!(error = %err, "No Java runtime found"),
+        }
+    }
+
+    let mut report = match install.version.clone() {
+        LoaderVersion::Fabric(version) => fabric::install(install.with_version(version)).await?,
+        LoaderVersion::Forge(version) => forge::install(install.with_version(version)).await?,
+        LoaderVersion::Quilt(version) => quilt::install(install.with_version(version)).await?,
+    };
+
+    if side == Side::Server {
+        utils::write_eula(&dir, accept_eula).await?;
+        report.files.push(dir.join("eula.txt"));
+    }
+    report.warnings.extend(java_warning);
+    if keep_hashed {
+        report.warnings.push(
+            "Kept the org.quiltmc:hashed libraries, this install is for debugging only".to_string(),
+        );
+    }
+
+    progress.report("Done", 1.0);
+
+    report.elapsed = started.elapsed();
+
+    Ok(report)
+}
+
+/// Estimates the download size of an install, `None` if the loader can't tell beforehand.
+pub async fn estimate_download(
+    urls: urls::BaseUrls,
+    mirrors: maven::Mirrors,
+    version: LoaderVersion,
     side: Side,
     dir: PathBuf,
     minecraft: minecraft::Version,
-    generate: bool,
-) -> anyhow::Result<()> {
-    if !dir.exists() {
-        anyhow::bail!("Installation directory doesn't exist: {}", dir.display());
+) -> anyhow::Result<Option<DownloadEstimate>> {
+    match (version, side) {
+        (LoaderVersion::Quilt(version), Side::Server) => {
+            quilt::estimate_download(&urls, &mirrors, &dir, &minecraft, &version)
+                .await
+                .map(Some)
+        }
+        _ => Ok(None),
     }
+}
 
-    match loader {
-        LoaderVersion::Fabric(version) => {
-            fabric::install(Install { side, dir, minecraft, version, generate }).await
+/// Fetches the profile an install would be based on, after the same fixes an install applies,
+/// without writing anything.
+pub async fn fetch_profile(
+    urls: &urls::BaseUrls,
+    version: &LoaderVersion,
+    side: Side,
+    minecraft: &minecraft::Version,
+) -> anyhow::Result<serde_json::Value> {
+    match (version, side) {
+        (LoaderVersion::Quilt(version), Side::Client) => {
+            quilt::fetch_client_profile(urls, minecraft, version, false).await
+        }
+        (LoaderVersion::Quilt(version), Side::Server) => {
+            let profile = quilt::fetch_server_profile(urls, minecraft, version, false).await?;
+
+            Ok(serde_json::to_value(profile)?)
         }
-        LoaderVersion::Forge(version) => {
-            forge::install(Install { side, dir, minecraft, version, generate }).await
+        (LoaderVersion::Fabric(_) | LoaderVersion::Forge(_), _) => {
+            anyhow::bail!(
+                "Printing {:?} profiles is not supported yet",
+                version.loader()
+            )
         }
+    }
+}
+
+/// Lists library files left behind by other versions, only ever under `dir/libraries`.
+pub async fn stale_libraries(
+    urls: &urls::BaseUrls,
+    version: &LoaderVersion,
+    dir: &Path,
+    minecraft: &minecraft::Version,
+) -> anyhow::Result<Vec<PathBuf>> {
+    match version {
         LoaderVersion::Quilt(version) => {
-            quilt::install(Install { side, dir, minecraft, version, generate }).await
+            quilt::stale_libraries(urls, dir, minecraft, version).await
+        }
+        LoaderVersion::Fabric(_) | LoaderVersion::Forge(_) => {
+            anyhow::bail!(
+                "Cleaning {:?} installs is not supported yet",
+                version.loader()
+            )
+        }
+    }
+}
+
+pub async fn verify(
+    loader: Loader,
+    side: Side,
+    dir: &Path,
+    versions: Option<(&str, &str)>,
+) -> anyhow::Result<Vec<String>> {
+    if !dir.exists() {
+        anyhow::bail!("Installation directory doesn't exist: {}", dir.display());
+    }
+
+    match loader {
+        Loader::Quilt => quilt::verify(side, dir, versions).await,
+        Loader::Fabric | Loader::Forge => {
+            anyhow::bail!("Verifying {:?} installs is not supported yet", loader)
         }
     }
 }
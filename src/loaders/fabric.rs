@@ -1,46 +1,127 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap};
 
-use iced::{pick_list, Alignment, Column, Command, Element, Length, PickList, Row, Text};
+use iced::{
+    button, pick_list, text_input, Alignment, Button, Checkbox, Column, Command, Element, Length,
+    PickList, Row, Text,
+};
 use iced_native::command::Action;
 
-use crate::loaders::{minecraft, Install};
+use crate::{
+    gui::autocomplete::{self, Autocomplete},
+    loaders::{minecraft, urls::BaseUrls, Install, InstallReport},
+    utils::{self, i18n::tr},
+};
 
-pub static GAME: &str = "https://meta.fabricmc.net/v2/versions/game";
-pub static MAVEN: &str = "https://maven.fabricmc.net/";
-pub static META: &str = "https://meta.fabricmc.net/v2/versions/loader";
+pub static MAVEN: &str = "https://maven.fabricmc.net";
+pub static META: &str = "https://meta.fabricmc.net/v2";
+
+pub static LEGACY_MAVEN: &str = "https://maven.legacyfabric.net";
+pub static LEGACY_META: &str = "https://meta.legacyfabric.net/v2";
+
+/// Whose Fabric builds are installed, Legacy Fabric ports the loader back to versions before
+/// 1.14 and mirrors the shape of Fabric's meta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flavor {
+    #[default]
+    Fabric,
+    LegacyFabric,
+}
+
+impl Flavor {
+    pub const ALL: [Flavor; 2] = [Flavor::Fabric, Flavor::LegacyFabric];
+
+    /// The base URLs with Fabric's pointed at this flavor's services.
+    pub fn urls(self, urls: &BaseUrls) -> BaseUrls {
+        match self {
+            Flavor::Fabric => urls.clone(),
+            Flavor::LegacyFabric => BaseUrls {
+                fabric_meta: LEGACY_META.to_string(),
+                fabric_maven: LEGACY_MAVEN.to_string(),
+                ..urls.clone()
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Flavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Flavor::Fabric => "Fabric",
+            Flavor::LegacyFabric => "Legacy Fabric",
+        })
+    }
+}
 
 pub struct Commands;
 
 impl Commands {
     #[tracing::instrument(skip_all, err)]
-    pub async fn fetch_minecraft() -> anyhow::Result<Vec<minecraft::Version>> {
-        Ok(reqwest::get(GAME).await?.json().await?)
+    pub async fn fetch_minecraft(urls: BaseUrls) -> anyhow::Result<Vec<minecraft::Version>> {
+        let url = format!("{}/versions/game", urls.fabric_meta);
+        let versions = utils::http::get(url).await?.json().await?;
+
+        Ok(minecraft::add_types(&urls, versions).await)
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    pub async fn fetch_versions(urls: BaseUrls) -> anyhow::Result<Vec<Version>> {
+        let url = format!("{}/versions/loader", urls.fabric_meta);
+        let mut versions: Vec<Version> = utils::http::get(url).await?.json().await?;
+
+        // Fabric doesn't promote builds, the newest stable one is the de-facto recommendation
+        if let Some(version) = versions.iter_mut().find(|v| v.stable) {
+            version.recommended = true;
+        }
+
+        Ok(versions)
     }
 
+    /// Loader versions with a profile for `minecraft`, meta only lists the ones it can build one
+    /// for.
     #[tracing::instrument(skip_all, err)]
-    pub async fn fetch_versions() -> anyhow::Result<Vec<Version>> {
-        Ok(reqwest::get(META).await?.json().await?)
+    pub async fn fetch_supported(urls: BaseUrls, minecraft: String) -> anyhow::Result<Vec<String>> {
+        let url = format!("{}/versions/loader/{}", urls.fabric_meta, minecraft);
+        let entries: Vec<serde_json::Value> = utils::http::get(url)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(entries
+            .iter()
+            .filter_map(|entry| entry["loader"]["version"].as_str())
+            .map(str::to_string)
+            .collect())
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[derive(serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct Version {
     pub separator: String,
     pub build: i64,
     pub maven: String,
     pub version: String,
     pub stable: bool,
+    #[serde(skip)]
+    pub recommended: bool,
 }
 
 impl std::fmt::Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.version.fmt(f)
+        if self.recommended {
+            write!(f, "{} (recommended)", self.version)
+        } else {
+            self.version.fmt(f)
+        }
     }
 }
 
-pub async fn install(_install: Install<Version>) -> anyhow::Result<()> {
-    Ok(())
+pub async fn install(install: Install<Version>) -> anyhow::Result<InstallReport> {
+    anyhow::bail!(
+        "Installing Fabric {} is not supported yet",
+        install.version.version
+    )
 }
 
 #[derive(Debug)]
@@ -51,6 +132,7 @@ pub enum Message {
 
     SetMinecraft(anyhow::Result<Vec<minecraft::Version>>),
     SetVersions(anyhow::Result<Vec<Version>>),
+    SetSupported(String, anyhow::Result<Vec<String>>),
 }
 
 #[allow(clippy::from_over_into)]
@@ -64,19 +146,50 @@ impl Into<Command<Message>> for Message {
 pub enum Interaction {
     Minecraft(minecraft::Interaction),
 
+    SelectFlavor(Flavor),
     SelectVersion(Version),
+    ShowBetas(bool),
+    Autocomplete(autocomplete::Event),
+
+    Refresh,
 }
 
 #[derive(Debug, Default)]
 pub struct State {
     pub minecraft: minecraft::State,
 
+    pub flavor: Flavor,
+    pub flavor_pick_list: pick_list::State<Flavor>,
+
     pub pick_list: pick_list::State<Version>,
     pub versions: Vec<Version>,
     pub selected_version: Option<Version>,
+    pub show_betas: bool,
+    pub autocomplete: Autocomplete,
+    /// Loader versions supporting each snapshot looked up so far, not every build does.
+    pub supported: HashMap<String, Vec<String>>,
+
+    /// Why the version lists are empty, shown next to a refresh button.
+    pub status: Option<String>,
+    pub refresh_button: button::State,
 }
 
 impl State {
+    pub fn fetch(urls: &BaseUrls) -> Command<Message> {
+        Command::batch([
+            Command::perform(
+                Commands::fetch_minecraft(urls.clone()),
+                Message::SetMinecraft,
+            ),
+            Command::perform(Commands::fetch_versions(urls.clone()), Message::SetVersions),
+        ])
+    }
+
+    /// The base URLs with Fabric's pointed at the selected flavor.
+    pub fn urls(&self, urls: &BaseUrls) -> BaseUrls {
+        self.flavor.urls(urls)
+    }
+
     pub fn selected_version(&self) -> Option<Version> {
         self.selected_version.as_ref().cloned()
     }
@@ -85,16 +198,158 @@ impl State {
         self.minecraft.selected_version.as_ref().cloned()
     }
 
+    pub fn set_show_unstable(&mut self, show: bool) {
+        self.minecraft.set_show_unstable(show);
+        self.show_betas = show;
+    }
+
+    /// Selects the versions of an earlier install, `false` if either isn't listed anymore.
+    pub fn select_existing(&mut self, version: &str, minecraft: &str) -> bool {
+        let version = self.versions.iter().find(|v| v.version == version);
+        let minecraft = self
+            .minecraft
+            .versions
+            .iter()
+            .find(|v| v.version == minecraft);
+
+        match (version, minecraft) {
+            (Some(version), Some(minecraft)) => {
+                self.selected_version = Some(version.clone());
+                self.minecraft.selected_version = Some(minecraft.clone());
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Looks up which loader versions support the selected Minecraft version, only needed for
+    /// snapshots as every build supports the releases meta lists.
+    pub fn fetch_supported(&self, urls: &BaseUrls) -> Command<Message> {
+        match &self.minecraft.selected_version {
+            Some(selected)
+                if selected.kind() != minecraft::VersionType::Release
+                    && !self.supported.contains_key(&selected.version) =>
+            {
+                let version = selected.version.clone();
+
+                Command::perform(
+                    Commands::fetch_supported(urls.clone(), version.clone()),
+                    move |result| Message::SetSupported(version, result),
+                )
+            }
+            _ => Command::none(),
+        }
+    }
+
+    /// The loader versions the selected snapshot is limited to, `None` for releases and while
+    /// they're still being looked up.
+    fn supported_versions(&self) -> Option<&[String]> {
+        let minecraft = self.minecraft.selected_version.as_ref()?;
+
+        self.supported.get(&minecraft.version).map(Vec::as_slice)
+    }
+
+    /// The loader versions on offer, leaving out betas unless shown and builds the selected
+    /// snapshot isn't supported by.
+    fn offered_versions(&self) -> Vec<Version> {
+        let supported = self.supported_versions();
+
+        self.versions
+            .iter()
+            .filter(|v| self.show_betas || v.stable)
+            .filter(|v| supported.map_or(true, |supported| supported.contains(&v.version)))
+            .cloned()
+            .collect()
+    }
+
+    /// Moves the highlight of whichever version input has the keyboard focus.
+    pub fn move_highlight(&mut self, down: bool) {
+        self.minecraft.move_highlight(down);
+
+        let versions = self.offered_versions();
+        self.autocomplete.move_highlight(down, &versions);
+    }
+
+    fn recommended_version(&self) -> Option<Version> {
+        self.versions
+            .iter()
+            .find(|v| v.recommended)
+            .or_else(|| self.versions.iter().find(|v| v.stable))
+            .cloned()
+    }
+
+    /// Goes back to the recommended build on a stable Minecraft version, for simple mode.
+    pub fn select_recommended(&mut self) {
+        self.minecraft.select_allowed();
+        self.selected_version = self.recommended_version();
+        self.select_supported();
+    }
+
+    /// Moves the selection to a supported loader version if the selected one isn't.
+    fn select_supported(&mut self) {
+        // Borrowing the fields directly leaves the selection free to change
+        let supported = self
+            .minecraft
+            .selected_version
+            .as_ref()
+            .and_then(|minecraft| self.supported.get(&minecraft.version));
+        let supported = match supported {
+            Some(supported) => supported,
+            None => return,
+        };
+
+        let selected = self.selected_version.as_ref();
+        if selected.map_or(true, |v| !supported.contains(&v.version)) {
+            self.selected_version = self
+                .versions
+                .iter()
+                .find(|v| supported.contains(&v.version))
+                .cloned();
+        }
+    }
+
+    pub fn carry_over_minecraft(
+        &mut self,
+        versions: &[minecraft::Version],
+        selected: Option<&minecraft::Version>,
+    ) {
+        self.minecraft.carry_over(versions, selected);
+    }
+
     pub fn update_interaction(&mut self, interaction: Interaction) -> Command<Message> {
         match interaction {
             Interaction::Minecraft(interaction) => {
-                return self
+                let command = self
                     .minecraft
                     .update_interaction(interaction)
-                    .map(Message::Minecraft)
+                    .map(Message::Minecraft);
+                self.select_supported();
+
+                return command;
             }
 
+            // Neither list carries over, the flavors have their own builds and Minecraft versions
+            Interaction::SelectFlavor(flavor) => {
+                self.flavor = flavor;
+                self.versions.clear();
+                self.selected_version = None;
+                self.minecraft.versions.clear();
+                self.minecraft.selected_version = None;
+                self.supported.clear();
+                self.status = None;
+            }
             Interaction::SelectVersion(version) => self.selected_version = Some(version),
+            Interaction::ShowBetas(show) => self.show_betas = show,
+            Interaction::Autocomplete(event) => {
+                let versions = self.offered_versions();
+                if let Some(version) = self.autocomplete.update(event, &versions) {
+                    self.selected_version = Some(version);
+                }
+            }
+
+            // The GUI does the fetching as it knows where from, also after picking a flavor
+            Interaction::Refresh => self.status = None,
         }
 
         Command::none()
@@ -113,8 +368,19 @@ impl State {
 
             Message::SetMinecraft(result) => {
                 match result {
+                    Ok(versions) if versions.is_empty() => {
+                        self.status = Some(format!(
+                            "No Minecraft versions available from {} meta, try again later",
+                            self.flavor
+                        ));
+                    }
                     Ok(versions) => self.minecraft.versions = versions,
-                    Err(error) => return Message::Error(error).into(),
+                    Err(error) => {
+                        self.status =
+                            Some(format!("Failed to fetch Minecraft versions: {}", error));
+
+                        return Message::Error(error).into();
+                    }
                 }
 
                 if self.minecraft.selected_version.is_none() {
@@ -124,39 +390,143 @@ impl State {
             }
             Message::SetVersions(result) => {
                 match result {
+                    Ok(versions) if versions.is_empty() => {
+                        self.status = Some(format!(
+                            "No loader versions available from {} meta, try again later",
+                            self.flavor
+                        ));
+                    }
                     Ok(versions) => self.versions = versions,
-                    Err(error) => return Message::Error(error).into(),
+                    Err(error) => {
+                        self.status = Some(format!("Failed to fetch loader versions: {}", error));
+
+                        return Message::Error(error).into();
+                    }
                 }
 
                 if self.selected_version.is_none() {
-                    self.selected_version = self.versions.first().cloned();
+                    self.selected_version = self.recommended_version();
                 }
             }
+            Message::SetSupported(minecraft, result) => match result {
+                Ok(versions) => {
+                    self.supported.insert(minecraft, versions);
+                    self.select_supported();
+                }
+                // Every build stays on offer, the install fails with a clear error if need be
+                Err(error) => return Message::Error(error).into(),
+            },
         }
 
         Command::none()
     }
 
-    pub fn view(&mut self) -> Element<'_, Interaction> {
-        Column::new()
-            .push(self.minecraft.view().map(Interaction::Minecraft))
+    /// The text inputs `view` shows, in the order it lays them out.
+    pub fn text_inputs(&mut self, expert: bool) -> Vec<&mut text_input::State> {
+        let mut inputs = self.minecraft.text_inputs(expert);
+        if expert {
+            inputs.push(self.autocomplete.input());
+        }
+
+        inputs
+    }
+
+    /// Simple mode names the build that gets installed instead of offering every build.
+    pub fn view(&mut self, expert: bool) -> Element<'_, Interaction> {
+        let supported = self.supported_versions().map(<[String]>::to_vec);
+        let versions = self.offered_versions();
+
+        let column = Column::new()
             .push(
                 Row::new()
-                    .push(Text::new("Loader version:").width(Length::Units(140)))
+                    .push(Text::new(tr("fabric_flavor")).width(Length::Units(140)))
+                    .push(
+                        PickList::new(
+                            &mut self.flavor_pick_list,
+                            Flavor::ALL.to_vec(),
+                            Some(self.flavor),
+                            Interaction::SelectFlavor,
+                        )
+                        .width(Length::Fill),
+                    )
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .padding(5),
+            )
+            .push(self.minecraft.view(expert).map(Interaction::Minecraft));
+        let column = if !expert {
+            let selected = match &self.selected_version {
+                Some(version) => version.to_string(),
+                None => tr("no_version_yet").to_string(),
+            };
+
+            column.push(
+                Row::new()
+                    .push(Text::new(tr("loader_version")).width(Length::Units(140)))
+                    .push(Text::new(selected))
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .padding(5),
+            )
+        } else {
+            let (input, matches) = self.autocomplete.view(&versions, Interaction::Autocomplete);
+
+            let column = column.push(
+                Row::new()
+                    .push(Text::new(tr("loader_version")).width(Length::Units(140)))
+                    .push(input)
                     .push(
                         PickList::new(
                             &mut self.pick_list,
-                            Cow::from(self.versions.clone()),
+                            Cow::from(versions),
                             self.selected_version.clone(),
                             Interaction::SelectVersion,
                         )
                         .width(Length::Fill),
                     )
+                    .push(Checkbox::new(
+                        self.show_betas,
+                        tr("show_betas"),
+                        Interaction::ShowBetas,
+                    ))
                     .width(Length::Fill)
                     .align_items(Alignment::Center)
                     .spacing(5)
                     .padding(5),
-            )
-            .into()
+            );
+            match matches {
+                Some(matches) => column.push(autocomplete::indent(matches)),
+                None => column,
+            }
+        };
+
+        // Loaders take a while to support a new snapshot
+        let column = match &supported {
+            Some(supported) if supported.is_empty() => column.push(
+                Row::new()
+                    .push(Text::new(tr("no_loader_for_snapshot")))
+                    .padding(5),
+            ),
+            _ => column,
+        };
+
+        match &self.status {
+            Some(status) => column
+                .push(
+                    Row::new()
+                        .push(Text::new(status.as_str()).width(Length::Fill))
+                        .push(
+                            Button::new(&mut self.refresh_button, Text::new(tr("refresh")))
+                                .on_press(Interaction::Refresh),
+                        )
+                        .align_items(Alignment::Center)
+                        .spacing(5)
+                        .padding(5),
+                )
+                .into(),
+            None => column.into(),
+        }
     }
 }
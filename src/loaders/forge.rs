@@ -1,15 +1,255 @@
-use iced::{Alignment, Command, Element, Length, Row};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::Context;
+use iced::{
+    button, pick_list, text_input, Alignment, Button, Column, Command, Element, Length, PickList,
+    Row, Text,
+};
 use iced_native::command::Action;
 
-use crate::loaders::{minecraft, Install};
+use crate::{
+    gui::autocomplete::{self, Autocomplete},
+    loaders::{minecraft, urls::BaseUrls, Install, InstallReport, Side},
+    utils::{self, i18n::tr},
+};
+
+pub static FILES: &str = "https://files.minecraftforge.net/net/minecraftforge/forge";
+pub static MAVEN: &str = "https://maven.minecraftforge.net";
+
+#[derive(serde::Deserialize)]
+struct Promotions {
+    promos: HashMap<String, String>,
+}
+
+pub struct Commands;
+
+impl Commands {
+    #[tracing::instrument(skip_all, err)]
+    pub async fn fetch_versions(urls: BaseUrls) -> anyhow::Result<Vec<Version>> {
+        let url = format!("{}/maven-metadata.json", urls.forge_files);
+        let metadata: HashMap<String, Vec<String>> = utils::http::get(url).await?.json().await?;
+
+        // Missing promotions only lose the highlight, so don't fail the whole list over them
+        let promos = match Self::fetch_promotions(&urls).await {
+            Ok(promotions) => promotions.promos,
+            Err(_) => HashMap::new(),
+        };
+
+        let mut minecraft = metadata.keys().cloned().collect::<Vec<_>>();
+        minecraft.sort_by_key(|version| std::cmp::Reverse(version_key(version)));
+
+        let versions = minecraft
+            .into_iter()
+            .flat_map(|minecraft| {
+                let builds = metadata.get(&minecraft).cloned().unwrap_or_default();
+
+                let recommended = promos.get(&format!("{}-recommended", minecraft)).cloned();
+
+                builds.into_iter().rev().map(move |full| {
+                    let version = full
+                        .strip_prefix(&format!("{}-", minecraft))
+                        .unwrap_or(&full)
+                        .to_string();
+
+                    Version {
+                        recommended: recommended.as_deref() == Some(version.as_str()),
+                        version,
+                        minecraft: minecraft.clone(),
+                        full,
+                    }
+                })
+            })
+            .collect();
+
+        Ok(versions)
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    async fn fetch_promotions(urls: &BaseUrls) -> anyhow::Result<Promotions> {
+        let url = format!("{}/promotions_slim.json", urls.forge_files);
+
+        Ok(utils::http::get(url).await?.json().await?)
+    }
+}
+
+/// Collects the Minecraft versions Forge has builds for, newest first.
+pub fn minecraft_versions(versions: &[Version]) -> Vec<minecraft::Version> {
+    let mut minecraft: Vec<minecraft::Version> = Vec::new();
+
+    for version in versions {
+        if minecraft.iter().all(|m| m.version != version.minecraft) {
+            minecraft.push(minecraft::Version {
+                version: version.minecraft.clone(),
+                stable: !version.minecraft.contains("pre"),
+                kind: None,
+            });
+        }
+    }
+
+    minecraft
+}
+
+/// Splits a version like `1.7.10_pre4` into its numeric parts so they sort naturally.
+fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Version {
+    pub minecraft: String,
+    pub version: String,
+    /// The Maven version of the build, `{minecraft}-{version}` with the odd legacy suffix.
+    pub full: String,
+    #[serde(skip)]
+    pub recommended: bool,
+}
+
+impl Version {
+    pub fn installer_url(&self, urls: &BaseUrls) -> String {
+        format!(
+            "{}/net/minecraftforge/forge/{}/forge-{}-installer.jar",
+            urls.forge_maven, self.full, self.full
+        )
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.recommended {
+            write!(f, "{} (recommended)", self.version)
+        } else {
+            self.version.fmt(f)
+        }
+    }
+}
+
+/// Installs Forge by running the official installer jar headlessly.
+///
+/// The installer handles both the modern processor pipeline and legacy universal jars, it also
+/// creates its own launcher profile so `install.generate_profile` is not used for clients.
+#[tracing::instrument(skip_all, err)]
+pub async fn install(install: Install<Version>) -> anyhow::Result<InstallReport> {
+    let java = utils::java::find_or(install.java.as_deref())
+        .await
+        .context("The Forge installer needs Java")?
+        .path;
+
+    // Download the installer into a temporary location, it writes its log next to itself
+    let installer_dir = InstallerDir::create()?;
+    let installer_path = installer_dir
+        .path()
+        .join(format!("forge-{}-installer.jar", install.version.full));
+
+    let url = install.version.installer_url(&install.urls);
+
+    tracing::info!(url = ?url, "Downloading Forge installer");
+    install
+        .progress
+        .report_waiting("Downloading Forge installer");
+
+    let res = utils::http::get(url)
+        .await?
+        .error_for_status()
+        .context("Forge installer download failed")?;
+
+    let bytes = res.bytes().await?;
+    tokio::fs::write(&installer_path, &bytes[..]).await?;
+
+    let flag = match install.side {
+        Side::Client => "--installClient",
+        Side::Server => "--installServer",
+    };
+
+    tracing::info!(java = ?java, flag, "Running Forge installer");
+    install.progress.report_waiting("Running Forge installer");
+
+    let output = tokio::process::Command::new(&java)
+        .arg("-jar")
+        .arg(&installer_path)
+        .arg(flag)
+        .arg(&install.dir)
+        .current_dir(&install.dir)
+        .stdin(Stdio::null())
+        // A cancelled install shouldn't leave the installer running on its own
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run the Forge installer")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        tracing::info!(target: "forge-installer", "{}", line);
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for line in stderr.lines() {
+        tracing::warn!(target: "forge-installer", "{}", line);
+    }
+
+    if !output.status.success() {
+        let reason = stderr
+            .lines()
+            .chain(stdout.lines())
+            .filter(|line| !line.trim().is_empty())
+            .last()
+            .unwrap_or("no output");
+
+        anyhow::bail!("Forge installer failed ({}): {}", output.status, reason);
+    }
+
+    // The installer decides what it writes, so there's nothing more specific to report
+    Ok(InstallReport::default())
+}
+
+/// A temporary directory for one run of the Forge installer, removed with the installer and its
+/// log however the install ends, even when it's dropped halfway.
+struct InstallerDir(PathBuf);
+
+impl InstallerDir {
+    fn create() -> std::io::Result<Self> {
+        // Unique per run, so concurrent installs of the same version don't share an installer
+        static RUNS: AtomicU64 = AtomicU64::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "anymc-installer-forge-{}-{}",
+            std::process::id(),
+            RUNS.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self(dir))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
 
-pub async fn install(_install: Install<bool>) -> anyhow::Result<()> {
-    Ok(())
+impl Drop for InstallerDir {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_dir_all(&self.0) {
+            tracing::warn!(path = ?self.0, error = ?err, "Failed to remove Forge installer");
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum Message {
     Error(anyhow::Error),
+
+    Minecraft(minecraft::Message),
+
+    SetVersions(anyhow::Result<Vec<Version>>),
 }
 
 #[allow(clippy::from_over_into)]
@@ -20,22 +260,120 @@ impl Into<Command<Message>> for Message {
 }
 
 #[derive(Debug, Clone)]
-pub enum Interaction {}
+pub enum Interaction {
+    Minecraft(minecraft::Interaction),
+
+    SelectVersion(Version),
+    Autocomplete(autocomplete::Event),
+
+    Refresh,
+}
 
 #[derive(Debug, Default)]
-pub struct State {}
+pub struct State {
+    pub minecraft: minecraft::State,
+
+    pub pick_list: pick_list::State<Version>,
+    pub versions: Vec<Version>,
+    pub selected_version: Option<Version>,
+    pub autocomplete: Autocomplete,
+
+    /// Why the version lists are empty, shown next to a refresh button.
+    pub status: Option<String>,
+    pub refresh_button: button::State,
+}
 
 impl State {
-    pub fn selected_version(&self) -> Option<bool> {
-        todo!()
+    pub fn fetch(urls: &BaseUrls) -> Command<Message> {
+        Command::perform(Commands::fetch_versions(urls.clone()), Message::SetVersions)
+    }
+
+    pub fn selected_version(&self) -> Option<Version> {
+        self.selected_version.as_ref().cloned()
     }
 
     pub fn selected_minecraft(&self) -> Option<minecraft::Version> {
-        todo!()
+        self.minecraft.selected_version.as_ref().cloned()
+    }
+
+    /// Moves the highlight of whichever version input has the keyboard focus.
+    pub fn move_highlight(&mut self, down: bool) {
+        self.minecraft.move_highlight(down);
+
+        let versions = self.minecraft_versions().cloned().collect::<Vec<_>>();
+        self.autocomplete.move_highlight(down, &versions);
+    }
+
+    /// Forge builds are tied to a single Minecraft version, so only offer the matching ones.
+    fn minecraft_versions(&self) -> impl Iterator<Item = &Version> {
+        let minecraft = self.minecraft.selected_version.as_ref();
+
+        self.versions
+            .iter()
+            .filter(move |v| minecraft.map(|m| m.version == v.minecraft).unwrap_or(false))
+    }
+
+    fn reset_selected_version(&mut self) {
+        let minecraft = self.minecraft.selected_version.as_ref();
+
+        let matches = match (&self.selected_version, minecraft) {
+            (Some(selected), Some(minecraft)) => selected.minecraft == minecraft.version,
+            _ => false,
+        };
+
+        if !matches {
+            self.selected_version = self
+                .minecraft_versions()
+                .find(|v| v.recommended)
+                .or_else(|| self.minecraft_versions().next())
+                .cloned();
+        }
+    }
+
+    pub fn set_show_unstable(&mut self, show: bool) {
+        self.minecraft.set_show_unstable(show);
+    }
+
+    /// Goes back to the recommended build on a stable Minecraft version, for simple mode.
+    pub fn select_recommended(&mut self) {
+        self.minecraft.select_allowed();
+        self.selected_version = None;
+        self.reset_selected_version();
+    }
+
+    pub fn carry_over_minecraft(
+        &mut self,
+        versions: &[minecraft::Version],
+        selected: Option<&minecraft::Version>,
+    ) {
+        self.minecraft.carry_over(versions, selected);
+        self.reset_selected_version();
     }
 
-    pub fn update_interaction(&mut self, _interaction: Interaction) -> Command<Message> {
-        // match interaction {}
+    pub fn update_interaction(&mut self, interaction: Interaction) -> Command<Message> {
+        match interaction {
+            Interaction::Minecraft(interaction) => {
+                let command = self
+                    .minecraft
+                    .update_interaction(interaction)
+                    .map(Message::Minecraft);
+
+                self.reset_selected_version();
+
+                return command;
+            }
+
+            Interaction::SelectVersion(version) => self.selected_version = Some(version),
+            Interaction::Autocomplete(event) => {
+                let versions = self.minecraft_versions().cloned().collect::<Vec<_>>();
+                if let Some(version) = self.autocomplete.update(event, &versions) {
+                    self.selected_version = Some(version);
+                }
+            }
+
+            // The GUI does the fetching as it knows where from
+            Interaction::Refresh => self.status = None,
+        }
 
         Command::none()
     }
@@ -43,17 +381,134 @@ impl State {
     pub fn update_message(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::Error(err) => eprintln!("{:#?}", err),
+
+            Message::Minecraft(message) => {
+                return self
+                    .minecraft
+                    .update_message(message)
+                    .map(Message::Minecraft)
+            }
+
+            Message::SetVersions(result) => {
+                match result {
+                    Ok(versions) if versions.is_empty() => {
+                        self.status = Some(
+                            "No versions available from the Forge maven, try again later"
+                                .to_string(),
+                        );
+                    }
+                    Ok(versions) => self.versions = versions,
+                    Err(error) => {
+                        self.status = Some(format!("Failed to fetch Forge versions: {}", error));
+
+                        return Message::Error(error).into();
+                    }
+                }
+
+                self.minecraft.versions = minecraft_versions(&self.versions);
+
+                if self.minecraft.selected_version.is_none() {
+                    self.minecraft.selected_version =
+                        self.minecraft.versions.iter().find(|v| v.stable).cloned();
+                }
+
+                self.reset_selected_version();
+            }
         }
 
         Command::none()
     }
 
-    pub fn view(&mut self) -> Element<'_, Interaction> {
-        Row::new()
-            .width(Length::Fill)
-            .align_items(Alignment::Center)
-            .spacing(5)
-            .padding(5)
-            .into()
+    /// The text inputs `view` shows, in the order it lays them out.
+    pub fn text_inputs(&mut self, expert: bool) -> Vec<&mut text_input::State> {
+        let mut inputs = self.minecraft.text_inputs(expert);
+        if expert {
+            inputs.push(self.autocomplete.input());
+        }
+
+        inputs
+    }
+
+    /// Simple mode names the build that gets installed instead of offering every build.
+    pub fn view(&mut self, expert: bool) -> Element<'_, Interaction> {
+        let versions = self.minecraft_versions().cloned().collect::<Vec<_>>();
+
+        let column = Column::new().push(self.minecraft.view(expert).map(Interaction::Minecraft));
+        let column = if !expert {
+            let selected = match &self.selected_version {
+                Some(version) => version.to_string(),
+                None => tr("no_version_yet").to_string(),
+            };
+
+            column.push(
+                Row::new()
+                    .push(Text::new(tr("loader_version")).width(Length::Units(140)))
+                    .push(Text::new(selected))
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .padding(5),
+            )
+        } else {
+            let (input, matches) = self.autocomplete.view(&versions, Interaction::Autocomplete);
+
+            let column = column.push(
+                Row::new()
+                    .push(Text::new(tr("loader_version")).width(Length::Units(140)))
+                    .push(input)
+                    .push(
+                        PickList::new(
+                            &mut self.pick_list,
+                            Cow::from(versions),
+                            self.selected_version.clone(),
+                            Interaction::SelectVersion,
+                        )
+                        .width(Length::Fill),
+                    )
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .padding(5),
+            );
+            match matches {
+                Some(matches) => column.push(autocomplete::indent(matches)),
+                None => column,
+            }
+        };
+
+        match &self.status {
+            Some(status) => column
+                .push(
+                    Row::new()
+                        .push(Text::new(status.as_str()).width(Length::Fill))
+                        .push(
+                            Button::new(&mut self.refresh_button, Text::new(tr("refresh")))
+                                .on_press(Interaction::Refresh),
+                        )
+                        .align_items(Alignment::Center)
+                        .spacing(5)
+                        .padding(5),
+                )
+                .into(),
+            None => column.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn installer_dir_is_unique_and_removed_on_drop() {
+        let first = InstallerDir::create().unwrap();
+        let second = InstallerDir::create().unwrap();
+        assert_ne!(first.path(), second.path());
+
+        let path = first.path().to_path_buf();
+        std::fs::write(path.join("forge-installer.jar.log"), "log").unwrap();
+
+        drop(first);
+        assert!(!path.exists());
     }
 }
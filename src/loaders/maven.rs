@@ -0,0 +1,430 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::Context;
+use reqwest::{RequestBuilder, Url};
+
+use crate::loaders::urls::BaseUrls;
+
+/// A Maven artifact as written in library lists, `group:name:version[:classifier]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MavenCoord {
+    pub group: String,
+    pub name: String,
+    pub version: String,
+    pub classifier: Option<String>,
+}
+
+impl MavenCoord {
+    /// The path of the artifact's jar relative to the root of a repository.
+    pub fn to_path(&self) -> String {
+        let file = match &self.classifier {
+            Some(classifier) => format!("{}-{}-{}.jar", self.name, self.version, classifier),
+            None => format!("{}-{}.jar", self.name, self.version),
+        };
+
+        format!(
+            "{}/{}/{}/{}",
+            self.group.replace('.', "/"),
+            self.name,
+            self.version,
+            file
+        )
+    }
+}
+
+impl FromStr for MavenCoord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split(':').collect::<Vec<_>>();
+        if parts.iter().any(|part| part.is_empty()) {
+            anyhow::bail!("Invalid Maven coordinate, empty part in: {}", s);
+        }
+
+        let (group, name, version, classifier) = match parts[..] {
+            [group, name, version] => (group, name, version, None),
+            [group, name, version, classifier] => (group, name, version, Some(classifier)),
+            _ => anyhow::bail!(
+                "Invalid Maven coordinate, expected group:name:version[:classifier]: {}",
+                s
+            ),
+        };
+
+        Ok(Self {
+            group: group.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            classifier: classifier.map(str::to_string),
+        })
+    }
+}
+
+impl fmt::Display for MavenCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.name, self.version)?;
+
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{}", classifier)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Basic auth login for a repository, the password is kept out of any `Debug` output so it
+/// can't end up in a log.
+#[derive(Clone)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// The Maven repositories libraries are downloaded from, tried in order until one has the file.
+#[derive(Debug, Clone)]
+pub struct Mirrors {
+    /// Group paths and the repositories hosting them, the first matching prefix wins.
+    routes: Vec<(String, Vec<String>)>,
+    /// Repositories for libraries no route matches.
+    fallback: Vec<String>,
+    /// Group paths and the repository publishing their snapshots, tried after everything else
+    /// for snapshot artifacts as pre-releases can reference ones that never made it to a release.
+    snapshots: Vec<(String, String)>,
+    /// Logins for repositories that need one, by host.
+    credentials: Vec<(String, Credentials)>,
+}
+
+impl Mirrors {
+    pub fn new(urls: &BaseUrls) -> Self {
+        Self {
+            routes: vec![("org/quiltmc".to_string(), vec![urls.quilt_maven.clone()])],
+            fallback: vec![urls.fabric_maven.clone()],
+            snapshots: vec![("org/quiltmc".to_string(), urls.quilt_snapshot_maven.clone())],
+            credentials: Vec::new(),
+        }
+    }
+
+    /// Adds a fallback repository for libraries under `prefix`, or for everything else when the
+    /// prefix is empty. Mirrors are only tried after the official repository failed.
+    pub fn add(&mut self, prefix: &str, url: &str) {
+        let prefix = prefix.replace('.', "/");
+        let url = url.to_string();
+
+        if prefix.is_empty() {
+            self.fallback.push(url);
+        } else if let Some((_, urls)) = self.routes.iter_mut().find(|(p, _)| *p == prefix) {
+            urls.push(url);
+        } else {
+            // Keep whatever served the group so far ahead of the new mirror
+            let mut urls = self.repositories(&prefix).to_vec();
+            urls.push(url);
+
+            self.routes.insert(0, (prefix, urls));
+        }
+    }
+
+    /// Parses a `--maven-mirror` value, either `<URL>` or `<GROUP>=<URL>`.
+    pub fn add_arg(&mut self, arg: &str) {
+        let (prefix, url) = split_arg(arg);
+
+        self.add(prefix, url);
+    }
+
+    /// Uses `credentials` for every request to `host`, replacing earlier ones for it.
+    pub fn add_credentials(&mut self, host: &str, credentials: Credentials) {
+        self.credentials.retain(|(h, _)| h != host);
+        self.credentials.push((host.to_string(), credentials));
+    }
+
+    /// Reads logins from a `.netrc` style file, `machine <host> login <user> password <pass>`.
+    ///
+    /// Macro definitions and keywords other tools added are skipped.
+    pub fn add_netrc(&mut self, contents: &str) -> anyhow::Result<()> {
+        // Host, login and password of each entry, `default` entries have no host to match
+        let mut entries: Vec<(Option<String>, Option<String>, Option<String>)> = Vec::new();
+
+        // A keyword still waiting for its value, which can be on the next line
+        let mut pending: Option<&str> = None;
+
+        let mut lines = contents.lines();
+        while let Some(line) = lines.next() {
+            for token in line.split_whitespace() {
+                if let Some(key) = pending.take() {
+                    let value = token.to_string();
+                    if key == "machine" {
+                        entries.push((Some(value), None, None));
+                        continue;
+                    }
+
+                    let entry = entries.last_mut().with_context(|| {
+                        format!("Found {} before any machine in netrc file", key)
+                    })?;
+                    match key {
+                        "login" => entry.1 = Some(value),
+                        "password" => entry.2 = Some(value),
+                        _ => {}
+                    }
+
+                    continue;
+                }
+
+                match token {
+                    "machine" | "login" | "password" | "account" => pending = Some(token),
+                    "default" => entries.push((None, None, None)),
+                    "macdef" => {
+                        // The macro's name ends the line, its body runs up to the next blank line
+                        for body in lines.by_ref() {
+                            if body.trim().is_empty() {
+                                break;
+                            }
+                        }
+                        break;
+                    }
+                    // Could be a secret whose keyword got mistyped, so the token itself isn't logged
+                    _ => tracing::warn!("Skipping unknown keyword in netrc file"),
+                }
+            }
+        }
+
+        if let Some(key) = pending {
+            anyhow::bail!("Missing value after {} in netrc file", key);
+        }
+
+        for entry in entries {
+            if let (Some(host), Some(username), Some(password)) = entry {
+                self.add_credentials(&host, Credentials { username, password });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the login for the host of `url` to a request, if there is one.
+    pub fn authorize(&self, url: &str, request: RequestBuilder) -> RequestBuilder {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string));
+
+        match self
+            .credentials
+            .iter()
+            .find(|(h, _)| Some(h) == host.as_ref())
+        {
+            Some((_, credentials)) => {
+                request.basic_auth(&credentials.username, Some(&credentials.password))
+            }
+            None => request,
+        }
+    }
+
+    fn repositories(&self, path: &str) -> &[String] {
+        self.routes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map(|(_, urls)| urls.as_slice())
+            .unwrap_or(&self.fallback)
+    }
+
+    /// Every URL an artifact at `path` can be downloaded from, most preferred first.
+    pub fn candidates(&self, path: &str) -> Vec<String> {
+        let snapshots = self
+            .snapshots
+            .iter()
+            .filter(|(prefix, _)| is_snapshot(path) && path.starts_with(prefix.as_str()))
+            .map(|(_, url)| url);
+
+        self.repositories(path)
+            .iter()
+            .chain(snapshots)
+            .map(|url| format!("{}/{}", url.trim_end_matches('/'), path))
+            .collect()
+    }
+}
+
+/// Whether an artifact path is a snapshot, by its version or a group only used for snapshots.
+fn is_snapshot(path: &str) -> bool {
+    path.split('/')
+        .any(|part| part.ends_with("-SNAPSHOT") || part == "snapshot" || part == "snapshots")
+}
+
+/// Splits a `--maven-mirror` value into its group and URL, the group is empty for plain URLs.
+pub fn split_arg(arg: &str) -> (&str, &str) {
+    match arg.split_once('=') {
+        Some((prefix, url)) if !prefix.contains("://") => (prefix, url),
+        _ => ("", arg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coord_without_classifier() {
+        let coord = "org.quiltmc:quilt-loader:0.17.0"
+            .parse::<MavenCoord>()
+            .unwrap();
+
+        assert_eq!(coord.group, "org.quiltmc");
+        assert_eq!(coord.name, "quilt-loader");
+        assert_eq!(coord.version, "0.17.0");
+        assert_eq!(coord.classifier, None);
+        assert_eq!(
+            coord.to_path(),
+            "org/quiltmc/quilt-loader/0.17.0/quilt-loader-0.17.0.jar"
+        );
+        assert_eq!(coord.to_string(), "org.quiltmc:quilt-loader:0.17.0");
+    }
+
+    #[test]
+    fn coord_with_classifier() {
+        let coord = "org.lwjgl:lwjgl:3.3.1:natives-linux"
+            .parse::<MavenCoord>()
+            .unwrap();
+
+        assert_eq!(coord.version, "3.3.1");
+        assert_eq!(coord.classifier.as_deref(), Some("natives-linux"));
+        assert_eq!(
+            coord.to_path(),
+            "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar"
+        );
+        assert_eq!(coord.to_string(), "org.lwjgl:lwjgl:3.3.1:natives-linux");
+    }
+
+    #[test]
+    fn coord_malformed() {
+        for coord in [
+            "",
+            "org.quiltmc",
+            "org.quiltmc:quilt-loader",
+            "org.quiltmc::0.17.0",
+            "org.quiltmc:quilt-loader:0.17.0:",
+            "org.quiltmc:quilt-loader:0.17.0:sources:extra",
+        ] {
+            assert!(coord.parse::<MavenCoord>().is_err(), "{:?} parsed", coord);
+        }
+    }
+
+    #[test]
+    fn coord_four_parts_keeps_classifier_out_of_version() {
+        let coord = "net.fabricmc:sponge-mixin:0.12.5+mixin.0.8.5:sources"
+            .parse::<MavenCoord>()
+            .unwrap();
+
+        assert_eq!(coord.version, "0.12.5+mixin.0.8.5");
+        assert_eq!(coord.classifier.as_deref(), Some("sources"));
+        assert_eq!(
+            coord.to_path(),
+            "net/fabricmc/sponge-mixin/0.12.5+mixin.0.8.5/sponge-mixin-0.12.5+mixin.0.8.5-sources.jar"
+        );
+    }
+
+    #[test]
+    fn snapshot_library_falls_back_to_snapshot_repository() {
+        let urls = BaseUrls::default();
+        let mirrors = Mirrors::new(&urls);
+
+        let path = "org/quiltmc/quilt-mappings/1.19-pre1+build.1-SNAPSHOT/quilt-mappings-1.19-pre1+build.1-SNAPSHOT.jar";
+        assert_eq!(
+            mirrors.candidates(path),
+            vec![
+                format!("{}/{}", urls.quilt_maven.trim_end_matches('/'), path),
+                format!(
+                    "{}/{}",
+                    urls.quilt_snapshot_maven.trim_end_matches('/'),
+                    path
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn release_library_skips_snapshot_repository() {
+        let urls = BaseUrls::default();
+        let mirrors = Mirrors::new(&urls);
+
+        let path = "org/quiltmc/quilt-loader/0.17.0/quilt-loader-0.17.0.jar";
+        assert_eq!(
+            mirrors.candidates(path),
+            vec![format!(
+                "{}/{}",
+                urls.quilt_maven.trim_end_matches('/'),
+                path
+            )]
+        );
+    }
+
+    fn logins(mirrors: &Mirrors) -> Vec<(&str, &str, &str)> {
+        mirrors
+            .credentials
+            .iter()
+            .map(|(host, credentials)| {
+                (
+                    host.as_str(),
+                    credentials.username.as_str(),
+                    credentials.password.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn netrc_entries() {
+        let mut mirrors = Mirrors::new(&BaseUrls::default());
+        mirrors
+            .add_netrc(
+                "machine maven.example.com login steve password hunter2\n\
+                 machine other.example.com\n  login alex\n  password secret\n\
+                 default login anonymous password guest\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            logins(&mirrors),
+            vec![
+                ("maven.example.com", "steve", "hunter2"),
+                ("other.example.com", "alex", "secret"),
+            ]
+        );
+    }
+
+    #[test]
+    fn netrc_skips_macros_and_unknown_keywords() {
+        let mut mirrors = Mirrors::new(&BaseUrls::default());
+        mirrors
+            .add_netrc(
+                "machine ftp.example.com login steve password hunter2 port 21\n\
+                 macdef init\n\
+                 cd /pub\n\
+                 machine not.a.host login nobody password nothing\n\
+                 \n\
+                 machine maven.example.com login alex password secret\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            logins(&mirrors),
+            vec![
+                ("ftp.example.com", "steve", "hunter2"),
+                ("maven.example.com", "alex", "secret"),
+            ]
+        );
+    }
+
+    #[test]
+    fn netrc_missing_value() {
+        let mut mirrors = Mirrors::new(&BaseUrls::default());
+
+        assert!(mirrors
+            .add_netrc("machine maven.example.com login steve password")
+            .is_err());
+    }
+}
@@ -1,13 +1,255 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap, path::Path};
 
-use iced::{pick_list, Alignment, Checkbox, Command, Element, Length, PickList, Row, Text};
+use anyhow::Context;
+use iced::{
+    pick_list, text_input, Alignment, Column, Command, Element, Length, PickList, Row, Text,
+};
 use iced_native::command::Action;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::{
+    gui::autocomplete::{self, Autocomplete},
+    loaders::urls::BaseUrls,
+    utils::{self, i18n::tr},
+};
+
+pub static MANIFEST: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+#[derive(Debug)]
+#[derive(serde::Deserialize)]
+struct Manifest {
+    versions: Vec<ManifestVersion>,
+}
+
+#[derive(Debug)]
+#[derive(serde::Deserialize)]
+struct ManifestVersion {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+}
+
+#[derive(Debug)]
+#[derive(serde::Deserialize)]
+struct VersionJson {
+    downloads: Downloads,
+    /// Missing from versions older than the launcher's bundled runtimes, which all run on 8.
+    #[serde(rename = "javaVersion")]
+    java_version: Option<JavaVersion>,
+}
+
+#[derive(Debug)]
+#[derive(serde::Deserialize)]
+struct JavaVersion {
+    #[serde(rename = "majorVersion")]
+    major_version: u32,
+}
+
+/// The files of a version by their key in its json, like `client` and `server`.
+pub type Downloads = HashMap<String, Download>;
+
+#[derive(Debug)]
 #[derive(serde::Deserialize)]
+pub struct Download {
+    pub url: String,
+}
+
+async fn fetch_manifest(urls: &BaseUrls) -> anyhow::Result<Manifest> {
+    Ok(utils::http::get(&urls.minecraft_manifest)
+        .await?
+        .json()
+        .await?)
+}
+
+/// Fills in the release channel of versions from Mojang's manifest, loader meta only says
+/// whether a version is stable. Versions the manifest doesn't list keep going by that.
+pub async fn add_types(urls: &BaseUrls, mut versions: Vec<Version>) -> Vec<Version> {
+    let manifest = match fetch_manifest(urls).await {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            tracing::warn!(error = ?err, "Failed to fetch version types from the manifest");
+            return versions;
+        }
+    };
+
+    for version in &mut versions {
+        let kind = manifest
+            .versions
+            .iter()
+            .find(|v| v.id == version.version)
+            .and_then(|v| VersionType::from_manifest(&v.kind));
+
+        if let Some(kind) = kind {
+            version.kind = Some(kind);
+            version.stable = kind == VersionType::Release;
+        }
+    }
+
+    versions
+}
+
+async fn fetch_version_json(urls: &BaseUrls, version: &str) -> anyhow::Result<VersionJson> {
+    let manifest = fetch_manifest(urls).await?;
+
+    let entry = manifest
+        .versions
+        .into_iter()
+        .find(|v| v.id == version)
+        .ok_or_else(|| anyhow::anyhow!("Minecraft {} is not in the version manifest", version))?;
+
+    Ok(utils::http::get(entry.url).await?.json().await?)
+}
+
+/// Looks up the vanilla jars of a version through Mojang's version manifest.
+#[tracing::instrument(skip_all, err)]
+pub async fn fetch_downloads(urls: &BaseUrls, version: &str) -> anyhow::Result<Downloads> {
+    Ok(fetch_version_json(urls, version).await?.downloads)
+}
+
+/// The Java release a version needs at least, as Mojang's manifest lists it for the launcher.
+///
+/// Falls back to [`Version::java_version`] when the manifest can't be reached or doesn't have
+/// the version.
+pub async fn fetch_java_version(urls: &BaseUrls, version: &Version) -> u32 {
+    match fetch_version_json(urls, &version.version).await {
+        Ok(json) => json.java_version.map_or(8, |java| java.major_version),
+        Err(err) => {
+            tracing::warn!(version = ?version.version, error = ?err, "Failed to look up the Java version, going by the version number");
+            version.java_version()
+        }
+    }
+}
+
+/// Downloads the vanilla server jar of a version.
+#[tracing::instrument(skip_all, err)]
+pub async fn download_server_jar(
+    urls: &BaseUrls,
+    version: &str,
+    path: &Path,
+) -> anyhow::Result<()> {
+    download_jar(urls, version, "server", path).await
+}
+
+/// Downloads the vanilla client jar of a version.
+#[tracing::instrument(skip_all, err)]
+pub async fn download_client_jar(
+    urls: &BaseUrls,
+    version: &str,
+    path: &Path,
+) -> anyhow::Result<()> {
+    download_jar(urls, version, "client", path).await
+}
+
+/// Downloads the jar listed under `key` in a version's downloads.
+async fn download_jar(
+    urls: &BaseUrls,
+    version: &str,
+    key: &str,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let jar = fetch_downloads(urls, version)
+        .await?
+        .remove(key)
+        .ok_or_else(|| anyhow::anyhow!("Minecraft {} has no {} jar", version, key))?;
+
+    tracing::info!(url = ?jar.url, "Downloading {} jar", key);
+
+    let res = utils::http::get(&jar.url)
+        .await?
+        .error_for_status()
+        .with_context(|| format!("Vanilla {} jar download failed", key))?;
+
+    let bytes = res.bytes().await?;
+    tokio::fs::write(path, &bytes[..]).await?;
+
+    Ok(())
+}
+
+/// Mojang's release channels, the `type` of a version in the manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionType {
+    Release,
+    Snapshot,
+    OldBeta,
+    OldAlpha,
+}
+
+impl VersionType {
+    /// Parses a manifest `type`, `None` for channels Mojang might add later.
+    fn from_manifest(kind: &str) -> Option<Self> {
+        match kind {
+            "release" => Some(VersionType::Release),
+            "snapshot" => Some(VersionType::Snapshot),
+            "old_beta" => Some(VersionType::OldBeta),
+            "old_alpha" => Some(VersionType::OldAlpha),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> Option<&'static str> {
+        match self {
+            VersionType::Release => None,
+            VersionType::Snapshot => Some("snapshot"),
+            VersionType::OldBeta => Some("old beta"),
+            VersionType::OldAlpha => Some("old alpha"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct Version {
     pub version: String,
     pub stable: bool,
+    /// Only known for versions found in Mojang's manifest.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<VersionType>,
+}
+
+impl Version {
+    pub fn kind(&self) -> VersionType {
+        match self.kind {
+            Some(kind) => kind,
+            None if self.stable => VersionType::Release,
+            None => VersionType::Snapshot,
+        }
+    }
+
+    /// The Java release the version needs at least, going by its release number.
+    ///
+    /// Snapshots like `24w14a` go by the year and week they came out in, anything else is
+    /// assumed to be recent. [`fetch_java_version`] has the release Mojang actually lists.
+    pub fn java_version(&self) -> u32 {
+        if let Some((year, week)) = snapshot_week(&self.version) {
+            return match (year, week) {
+                (25.., _) | (24, 14..) => 21,
+                (22.., _) => 17,
+                (21, 19..) => 16,
+                _ => 8,
+            };
+        }
+
+        let mut parts = self
+            .version
+            .split(|c: char| !c.is_ascii_digit())
+            .map(|part| part.parse::<u32>().ok());
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Some(1)), Some(Some(minor)), patch) => {
+                let patch = patch.flatten().unwrap_or(0);
+
+                match (minor, patch) {
+                    (21.., _) | (20, 5..) => 21,
+                    (18.., _) => 17,
+                    (17, _) => 16,
+                    _ => 8,
+                }
+            }
+            _ => 21,
+        }
+    }
 }
 
 impl std::fmt::Display for Version {
@@ -16,6 +258,66 @@ impl std::fmt::Display for Version {
     }
 }
 
+/// The year and week of a snapshot id like `24w14a`.
+fn snapshot_week(version: &str) -> Option<(u32, u32)> {
+    let (year, rest) = version.split_once('w')?;
+    let week = rest.get(..2)?;
+    if year.len() != 2 || !year.bytes().chain(week.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((year.parse().ok()?, week.parse().ok()?))
+}
+
+/// A version as shown in the pick list, suffixed with its channel unless it's a release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice(pub Version);
+
+impl std::fmt::Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.kind().label() {
+            Some(label) => write!(f, "{} ({})", self.0.version, label),
+            None => write!(f, "{}", self.0.version),
+        }
+    }
+}
+
+/// Which versions are listed, from only releases up to every historical alpha and beta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionFilter {
+    #[default]
+    Releases,
+    Snapshots,
+    All,
+}
+
+impl VersionFilter {
+    pub const ALL: [VersionFilter; 3] = [
+        VersionFilter::Releases,
+        VersionFilter::Snapshots,
+        VersionFilter::All,
+    ];
+
+    pub fn allows(self, version: &Version) -> bool {
+        match (self, version.kind()) {
+            (VersionFilter::All, _) => true,
+            (VersionFilter::Snapshots, VersionType::Release | VersionType::Snapshot) => true,
+            (VersionFilter::Releases, VersionType::Release) => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for VersionFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            VersionFilter::Releases => tr("releases_only"),
+            VersionFilter::Snapshots => tr("releases_and_snapshots"),
+            VersionFilter::All => tr("all_versions"),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum Message {
     Error(anyhow::Error),
@@ -31,22 +333,91 @@ impl Into<Command<Message>> for Message {
 #[derive(Debug, Clone)]
 pub enum Interaction {
     SelectVersion(Version),
-    ShowSnapshots(bool),
+    SelectFilter(VersionFilter),
+    Autocomplete(autocomplete::Event),
 }
 
 #[derive(Debug, Default)]
 pub struct State {
-    pub pick_list: pick_list::State<Version>,
+    pub pick_list: pick_list::State<Choice>,
     pub versions: Vec<Version>,
     pub selected_version: Option<Version>,
-    pub show_snapshots: bool,
+    pub filter: VersionFilter,
+    pub filter_pick_list: pick_list::State<VersionFilter>,
+    pub autocomplete: Autocomplete,
 }
 
 impl State {
+    /// Follows the GUI's unstable toggle, keeping historical versions listed if they were.
+    pub fn set_show_unstable(&mut self, show: bool) {
+        self.filter = match (show, self.filter) {
+            (false, _) => VersionFilter::Releases,
+            (true, VersionFilter::Releases) => VersionFilter::Snapshots,
+            (true, filter) => filter,
+        };
+    }
+
+    /// Replaces a selection the filter hides with the newest version it shows.
+    pub fn select_allowed(&mut self) {
+        let filter = self.filter;
+        if self
+            .selected_version
+            .as_ref()
+            .map_or(true, |v| !filter.allows(v))
+        {
+            self.selected_version = self.versions.iter().find(|v| filter.allows(v)).cloned();
+        }
+    }
+
+    /// Carries over the selection from another loader's list when switching loaders.
+    ///
+    /// Keeps the same version if this list has it, otherwise picks the newest version both
+    /// lists have in common, falling back to the newest stable version of this list.
+    pub fn carry_over(&mut self, versions: &[Version], selected: Option<&Version>) {
+        let selected = match selected {
+            Some(selected) => selected,
+            None => return,
+        };
+
+        let same = self.versions.iter().find(|v| v.version == selected.version);
+        let common = || {
+            self.versions
+                .iter()
+                .filter(|v| self.filter.allows(v))
+                .find(|v| versions.iter().any(|other| other.version == v.version))
+        };
+        let stable = || self.versions.iter().find(|v| v.stable);
+
+        if let Some(version) = same.or_else(common).or_else(stable) {
+            self.selected_version = Some(version.clone());
+        }
+    }
+
+    /// The versions the filter lets through, as they're listed.
+    fn choices(&self) -> Vec<Choice> {
+        self.versions
+            .iter()
+            .filter(|v| self.filter.allows(v))
+            .cloned()
+            .map(Choice)
+            .collect()
+    }
+
+    pub fn move_highlight(&mut self, down: bool) {
+        let choices = self.choices();
+        self.autocomplete.move_highlight(down, &choices);
+    }
+
     pub fn update_interaction(&mut self, interaction: Interaction) -> Command<Message> {
         match interaction {
             Interaction::SelectVersion(version) => self.selected_version = Some(version),
-            Interaction::ShowSnapshots(show) => self.show_snapshots = show,
+            Interaction::SelectFilter(filter) => self.filter = filter,
+            Interaction::Autocomplete(event) => {
+                let choices = self.choices();
+                if let Some(choice) = self.autocomplete.update(event, &choices) {
+                    self.selected_version = Some(choice.0);
+                }
+            }
         }
 
         Command::none()
@@ -60,32 +431,132 @@ impl State {
         Command::none()
     }
 
-    pub fn view(&mut self) -> Element<'_, Interaction> {
-        Row::new()
-            .push(Text::new("Minecraft version:").width(Length::Units(140)))
+    /// The text inputs `view` shows, in the order it lays them out.
+    pub fn text_inputs(&mut self, expert: bool) -> Vec<&mut text_input::State> {
+        if expert {
+            vec![self.autocomplete.input()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Only the pick list is shown in simple mode, without the input and the filter.
+    pub fn view(&mut self, expert: bool) -> Element<'_, Interaction> {
+        let choices = self.choices();
+
+        if !expert {
+            return Row::new()
+                .push(Text::new(tr("minecraft_version")).width(Length::Units(140)))
+                .push(
+                    PickList::new(
+                        &mut self.pick_list,
+                        Cow::from(choices),
+                        self.selected_version.clone().map(Choice),
+                        |choice| Interaction::SelectVersion(choice.0),
+                    )
+                    .width(Length::Fill),
+                )
+                .width(Length::Fill)
+                .align_items(Alignment::Center)
+                .spacing(5)
+                .padding(5)
+                .into();
+        }
+
+        let (input, matches) = self.autocomplete.view(&choices, Interaction::Autocomplete);
+
+        let row = Row::new()
+            .push(Text::new(tr("minecraft_version")).width(Length::Units(140)))
+            .push(input)
             .push(
                 PickList::new(
                     &mut self.pick_list,
-                    Cow::from_iter(
-                        self.versions
-                            .iter()
-                            .filter(|v| self.show_snapshots || v.stable)
-                            .cloned(),
-                    ),
-                    self.selected_version.clone(),
-                    Interaction::SelectVersion,
+                    Cow::from(choices),
+                    self.selected_version.clone().map(Choice),
+                    |choice| Interaction::SelectVersion(choice.0),
                 )
                 .width(Length::Fill),
             )
-            .push(Checkbox::new(
-                self.show_snapshots,
-                "Show snapshots",
-                Interaction::ShowSnapshots,
+            .push(PickList::new(
+                &mut self.filter_pick_list,
+                VersionFilter::ALL.to_vec(),
+                Some(self.filter),
+                Interaction::SelectFilter,
             ))
             .width(Length::Fill)
             .align_items(Alignment::Center)
             .spacing(5)
-            .padding(5)
-            .into()
+            .padding(5);
+
+        match matches {
+            Some(matches) => Column::new()
+                .push(row)
+                .push(autocomplete::indent(matches))
+                .into(),
+            None => row.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        Version {
+            version: version.to_string(),
+            stable: true,
+            kind: None,
+        }
+    }
+
+    #[test]
+    fn java_version_of_releases() {
+        for (minecraft, java) in [
+            ("1.8.9", 8),
+            ("1.16.5", 8),
+            ("1.17", 16),
+            ("1.17.1", 16),
+            ("1.18", 17),
+            ("1.20.4", 17),
+            ("1.20.5", 21),
+            ("1.21", 21),
+        ] {
+            assert_eq!(version(minecraft).java_version(), java, "{}", minecraft);
+        }
+    }
+
+    #[test]
+    fn java_version_of_snapshots() {
+        for (minecraft, java) in [
+            ("20w45a", 8),
+            ("21w18a", 8),
+            ("21w19a", 16),
+            ("21w44a", 16),
+            ("22w42a", 17),
+            ("24w13a", 17),
+            ("24w14a", 21),
+            ("25w02a", 21),
+        ] {
+            assert_eq!(version(minecraft).java_version(), java, "{}", minecraft);
+        }
+    }
+
+    #[test]
+    fn java_version_from_version_json() {
+        let json: VersionJson = serde_json::from_str(
+            r#"{
+                "downloads": { "server": { "url": "https://example.com/server.jar" } },
+                "javaVersion": { "component": "java-runtime-alpha", "majorVersion": 16 }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(json.java_version.unwrap().major_version, 16);
+
+        let json: VersionJson = serde_json::from_str(
+            r#"{ "downloads": { "client": { "url": "https://example.com/client.jar" } } }"#,
+        )
+        .unwrap();
+        assert!(json.java_version.is_none());
     }
 }
@@ -1,36 +1,78 @@
 use std::{
     borrow::Cow,
-    io::{Cursor, Write},
-    path::{Path, PathBuf},
+    collections::{HashMap, HashSet},
+    io::{Cursor, Read, Write},
+    path::{Component, Path, PathBuf},
+    process::Stdio,
+    time::Duration,
 };
 
 use anyhow::Context;
-use futures::{stream, StreamExt, TryStreamExt};
-use iced::{pick_list, Alignment, Checkbox, Column, Command, Element, Length, PickList, Row, Text};
+use futures::{future, stream, StreamExt, TryStreamExt};
+use iced::{
+    button, pick_list, text_input, Alignment, Button, Checkbox, Column, Command, Element, Length,
+    PickList, Row, Text,
+};
 use iced_native::command::Action;
-use tokio::fs::File;
-use zip::{write::FileOptions, CompressionMethod};
+use tokio::{fs::File, io::AsyncWriteExt};
+use zip::{write::FileOptions, DateTime};
 
 use crate::{
-    loaders::{fabric, minecraft, Install, LoaderVersion, Side},
-    utils,
+    gui::autocomplete::{self, Autocomplete},
+    loaders::{
+        maven::{MavenCoord, Mirrors},
+        minecraft,
+        urls::BaseUrls,
+        DownloadEstimate, Install, InstallReport, JarCompression, LoaderVersion, Reporter, Side,
+    },
+    lockfile::{self, LockedLibrary, Lockfile, Partial},
+    utils::{self, checksums::Checksums, cleanup::Cleanup, i18n::tr, throttle::Throttle},
 };
 
-pub static GAME: &str = "https://meta.quiltmc.org/v3/versions/game";
 pub static MAVEN: &str = "https://maven.quiltmc.org/repository/release";
-pub static META: &str = "https://meta.quiltmc.org/v3/versions/loader";
+pub static SNAPSHOT_MAVEN: &str = "https://maven.quiltmc.org/repository/snapshot";
+pub static META: &str = "https://meta.quiltmc.org/v3";
 
 pub struct Commands;
 
 impl Commands {
     #[tracing::instrument(skip_all, err)]
-    pub async fn fetch_minecraft() -> anyhow::Result<Vec<minecraft::Version>> {
-        Ok(reqwest::get(GAME).await?.json().await?)
+    pub async fn fetch_minecraft(urls: BaseUrls) -> anyhow::Result<Vec<minecraft::Version>> {
+        let url = format!("{}/versions/game", urls.quilt_meta);
+        let versions = utils::http::get(url).await?.json().await?;
+
+        Ok(minecraft::add_types(&urls, versions).await)
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    pub async fn fetch_versions(urls: BaseUrls) -> anyhow::Result<Vec<Version>> {
+        let url = format!("{}/versions/loader", urls.quilt_meta);
+        let mut versions: Vec<Version> = utils::http::get(url).await?.json().await?;
+
+        // Quilt doesn't promote builds, the newest non-beta one is the de-facto recommendation
+        if let Some(version) = versions.iter_mut().find(|v| !v.version.contains("beta")) {
+            version.recommended = true;
+        }
+
+        Ok(versions)
     }
 
+    /// Loader versions with a profile for `minecraft`, meta only lists the ones it can build one
+    /// for.
     #[tracing::instrument(skip_all, err)]
-    pub async fn fetch_versions() -> anyhow::Result<Vec<Version>> {
-        Ok(reqwest::get(META).await?.json().await?)
+    pub async fn fetch_supported(urls: BaseUrls, minecraft: String) -> anyhow::Result<Vec<String>> {
+        let url = format!("{}/versions/loader/{}", urls.quilt_meta, minecraft);
+        let entries: Vec<serde_json::Value> = utils::http::get(url)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(entries
+            .iter()
+            .filter_map(|entry| entry["loader"]["version"].as_str())
+            .map(str::to_string)
+            .collect())
     }
 }
 
@@ -64,18 +106,50 @@ pub struct ServerProfile {
     pub server_profile_type: String,
     #[serde(rename = "mainClass")]
     pub main_class: String,
-    #[serde(rename = "launcherMainClass")]
-    pub launcher_main_class: String,
+    /// Only in profiles of loaders that ship a server launcher.
+    #[serde(
+        rename = "launcherMainClass",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub launcher_main_class: Option<String>,
     pub arguments: Arguments,
     pub libraries: Vec<Library>,
 }
 
+impl ServerProfile {
+    /// The `Main-Class` of the launch jar.
+    ///
+    /// `mainClass` is Knot, which expects the game and libraries on the class path already.
+    /// `launcherMainClass` is the server launcher that sets that up from the jar's Class-Path
+    /// and then hands over to Knot, so it goes first when the profile has one. Profiles without
+    /// it predate the launcher and start Knot directly.
+    pub fn launch_jar_main_class(&self) -> &str {
+        self.launcher_main_class
+            .as_deref()
+            .filter(|class| !class.is_empty())
+            .unwrap_or(&self.main_class)
+    }
+}
+
 #[derive(Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Arguments {
     pub game: Vec<Option<serde_json::Value>>,
 }
 
+impl Arguments {
+    /// The plain game arguments, ones with rules (objects) depend on launcher features a start
+    /// script doesn't have and nulls carry nothing, so both are left out.
+    pub fn plain_game(&self) -> Vec<String> {
+        self.game
+            .iter()
+            .flatten()
+            .filter_map(|arg| arg.as_str().map(str::to_string))
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Library {
@@ -84,174 +158,843 @@ pub struct Library {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[derive(serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct Version {
     pub separator: String,
     pub build: u32,
     pub maven: String,
     pub version: String,
+    #[serde(skip)]
+    pub recommended: bool,
 }
 
 impl std::fmt::Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.version.fmt(f)
+        if self.recommended {
+            write!(f, "{} (recommended)", self.version)
+        } else {
+            self.version.fmt(f)
+        }
     }
 }
 
 #[tracing::instrument(skip_all, err)]
-pub async fn install(install: Install<Version>) -> anyhow::Result<()> {
+pub async fn install(install: Install<Version>) -> anyhow::Result<InstallReport> {
     match install.side {
-        Side::Client => install_client(install).await?,
-        Side::Server => install_server(install).await?,
+        Side::Client => install_client(install).await,
+        Side::Server => install_server(install).await,
     }
-
-    Ok(())
 }
 
 #[tracing::instrument(skip_all, err)]
-async fn install_client(install: Install<Version>) -> anyhow::Result<()> {
+async fn install_client(install: Install<Version>) -> anyhow::Result<InstallReport> {
     // Resolve profile directory
-    let profile_name = format!("quilt-loader-{}-{}", install.version, install.minecraft);
-    let mut profile_dir = install.dir.clone();
-    profile_dir.push("versions");
-    profile_dir.push(&profile_name);
+    let base_name = format!(
+        "quilt-loader-{}-{}",
+        install.version.version, install.minecraft
+    );
+    let versions_dir = install.dir.join("versions");
+
+    // Take the first free `-2`, `-3`, ... so the existing version is left untouched
+    let suffix = if install.keep_existing && versions_dir.join(&base_name).exists() {
+        (2..).find(|n| !versions_dir.join(format!("{}-{}", base_name, n)).exists())
+    } else {
+        None
+    };
+
+    let profile_name = match suffix {
+        Some(n) => format!("{}-{}", base_name, n),
+        None => base_name,
+    };
+    let profile_dir = versions_dir.join(&profile_name);
+    let json_path = profile_dir.join(format!("{}.json", &profile_name));
+    let jar_path = install
+        .placeholder_jar
+        .then(|| profile_dir.join(format!("{}.jar", &profile_name)));
 
-    // Delete existing profile
-    if profile_dir.exists() {
-        tokio::fs::remove_dir_all(&profile_dir).await?;
+    // Download launch json
+    install.progress.report_waiting("Downloading profile");
+    let mut profile = fetch_client_profile(
+        &install.urls,
+        &install.minecraft,
+        &install.version,
+        install.keep_hashed_libraries,
+    )
+    .await?;
+    if suffix.is_some() {
+        // The launcher only finds a version whose id matches its directory
+        profile["id"] = serde_json::Value::String(profile_name.clone());
     }
 
-    // Create directory
-    tokio::fs::create_dir_all(&profile_dir).await?;
+    let up_to_date = is_up_to_date(&install, &json_path, jar_path.as_deref(), &profile).await;
+    if up_to_date {
+        tracing::info!(profile = ?profile_name, "Profile is already installed, keeping it");
+    } else {
+        // Delete existing profile
+        if profile_dir.exists() {
+            tokio::fs::remove_dir_all(&profile_dir).await?;
+        }
 
-    // NOTE: This is an empty jar file to make the vanilla launcher happy
-    let mut jar_path = profile_dir.clone();
-    jar_path.push(format!("{}.jar", &profile_name));
-    File::create(jar_path).await?;
+        // Create directory
+        tokio::fs::create_dir_all(&profile_dir).await?;
+        install.cleanup.track(&profile_dir);
+
+        // NOTE: This is an empty jar file to make the vanilla launcher happy, it refuses to show a
+        // version that has no jar of its own even though the profile inherits the game jar. Other
+        // launchers (Prism, MultiMC) don't need it and some complain about it, so it can be
+        // skipped. Others again refuse an empty jar, for those it can be a copy of the vanilla one
+        // instead.
+        if let Some(jar_path) = &jar_path {
+            if install.vanilla_jar {
+                install.progress.report_waiting("Downloading client jar");
+                minecraft::download_client_jar(&install.urls, &install.minecraft.version, jar_path)
+                    .await?;
+            } else {
+                File::create(jar_path).await?;
+            }
+        }
 
-    // Create launch json
-    let mut json_path = profile_dir.clone();
-    json_path.push(format!("{}.json", &profile_name));
-    let mut file = File::create(json_path).await?;
+        // Create launch json
+        let mut file = File::create(&json_path).await?;
+        let response = install.json_format.serialize(&profile)?;
 
-    // Download launch json
-    let mut profile: ClientProfile = reqwest::get(format!(
-        "https://meta.quiltmc.org/v3/versions/loader/{}/{}/profile/json",
-        &install.minecraft, &install.version
-    ))
-    .await?
-    .json()
-    .await?;
+        install.progress.report("Writing files", 0.5);
+        tokio::io::copy(&mut response.as_bytes(), &mut file).await?;
+        file.flush().await?;
+        drop(file);
 
-    // Hack-Fix:
-    // Quilt-meta specifies both hashed and intermediary, but providing both to quilt-loader causes it to silently fail remapping.
-    // This really shouldn't be fixed here in the installer, but we need a solution now.
-    profile
-        .libraries
-        .retain(|lib| !lib.name.starts_with("org.quiltmc:hashed"));
-    let response = serde_json::to_string_pretty(&profile)?;
-    // End of hack-fix
+        // Only the placeholder has a known size to check
+        let placeholder = jar_path.as_deref().filter(|_| !install.vanilla_jar);
+        if let Err(err) = check_client_files(placeholder, &json_path).await {
+            tokio::fs::remove_dir_all(&profile_dir).await?;
+            return Err(err);
+        }
 
-    tokio::io::copy(&mut response.as_bytes(), &mut file).await?;
+        install.cleanup.finish(&profile_dir);
+    }
 
-    if install.generate {
-        utils::generate_profile(
+    // Launchers other than the vanilla one don't download the base version on their own, and
+    // then fail to start the profile without saying why
+    let mut warnings = Vec::new();
+    if let Some(base) = profile["inheritsFrom"].as_str() {
+        let base_json = install
+            .dir
+            .join("versions")
+            .join(base)
+            .join(format!("{}.json", base));
+
+        if !base_json.exists() {
+            tracing::warn!(version = ?base, "Base version of the profile is not installed");
+            warnings.push(format!(
+                "Minecraft {} isn't installed yet, start it once from the launcher before playing",
+                base
+            ));
+        }
+    }
+
+    let mut files = jar_path.into_iter().collect::<Vec<_>>();
+    files.push(json_path);
+
+    if install.generate_profile {
+        install.progress.report("Generating profile", 0.8);
+        files.push(install.dir.join("launcher_profiles.json"));
+
+        let merged = utils::generate_profile(
             install.dir,
             install.minecraft,
             LoaderVersion::Quilt(install.version),
+            suffix,
+            install.icon.as_deref(),
+            install.jvm_args.as_deref(),
+            install.json_format,
+            install.replace_profile,
         )
         .await?;
+        if merged {
+            tracing::info!(profile = ?profile_name, "Updated the existing launcher profile");
+        }
+    }
+
+    Ok(InstallReport {
+        profile_name: Some(profile_name),
+        up_to_date,
+        files,
+        warnings,
+        ..Default::default()
+    })
+}
+
+/// Downloads the launch json of a client, kept untyped so the written file has the same keys in
+/// the same order.
+///
+/// `keep_hashed` leaves the `org.quiltmc:hashed` libraries in, only for debugging remapping.
+pub async fn fetch_client_profile(
+    urls: &BaseUrls,
+    minecraft: &minecraft::Version,
+    version: &Version,
+    keep_hashed: bool,
+) -> anyhow::Result<serde_json::Value> {
+    let mut profile: serde_json::Value = get_profile(
+        format!(
+            "{}/versions/loader/{}/{}/profile/json",
+            urls.quilt_meta, minecraft, &version.version
+        ),
+        minecraft,
+        version,
+    )
+    .await?;
+
+    // Hack-Fix:
+    // Quilt-meta specifies both hashed and intermediary, but providing both to quilt-loader causes it to silently fail remapping.
+    // This really shouldn't be fixed here in the installer, but we need a solution now.
+    if !keep_hashed {
+        if let Some(libraries) = profile
+            .get_mut("libraries")
+            .and_then(serde_json::Value::as_array_mut)
+        {
+            libraries.retain(|lib| {
+                !lib["name"]
+                    .as_str()
+                    .map(|name| name.starts_with("org.quiltmc:hashed"))
+                    .unwrap_or(false)
+            });
+        }
+    }
+    // End of hack-fix
+
+    Ok(profile)
+}
+
+/// Whether the version directory already holds exactly what installing would write.
+///
+/// The json is compared parsed, so a profile written with a different `JsonFormat` still counts.
+async fn is_up_to_date(
+    install: &Install<Version>,
+    json: &Path,
+    jar: Option<&Path>,
+    profile: &serde_json::Value,
+) -> bool {
+    let installed = match tokio::fs::read_to_string(json).await {
+        Ok(installed) => installed,
+        Err(_) => return false,
+    };
+    if serde_json::from_str::<serde_json::Value>(&installed)
+        .ok()
+        .as_ref()
+        != Some(profile)
+    {
+        return false;
     }
 
+    let jar_len = match jar {
+        Some(jar) => tokio::fs::metadata(jar)
+            .await
+            .ok()
+            .map(|metadata| metadata.len()),
+        None => None,
+    };
+
+    match jar_len {
+        // A copied vanilla jar is never empty, a placeholder always is
+        Some(len) => install.vanilla_jar == (len != 0),
+        None => jar.is_none(),
+    }
+}
+
+/// Reads the written profile back to catch I/O failures that went unnoticed, like a full disk.
+async fn check_client_files(jar: Option<&Path>, json: &Path) -> anyhow::Result<()> {
+    if let Some(jar) = jar {
+        let jar_len = tokio::fs::metadata(jar)
+            .await
+            .context("Profile jar is missing after install")?
+            .len();
+        if jar_len != 0 {
+            anyhow::bail!("Profile jar has an unexpected size of {} bytes", jar_len);
+        }
+    }
+
+    let written = tokio::fs::read_to_string(json)
+        .await
+        .context("Failed to read back the profile json")?;
+    serde_json::from_str::<ClientProfile>(&written)
+        .context("Profile json was not written correctly")?;
+
     Ok(())
 }
 
+/// Fetches one of meta's profile endpoints, they answer 404 for combinations that aren't published.
+async fn get_profile<T: serde::de::DeserializeOwned>(
+    url: String,
+    minecraft: &minecraft::Version,
+    version: &Version,
+) -> anyhow::Result<T> {
+    let res = utils::http::get(url).await?;
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!(
+            "Quilt {} isn't published for Minecraft {} yet",
+            version.version,
+            minecraft
+        );
+    }
+
+    Ok(res.error_for_status()?.json().await?)
+}
+
+/// `keep_hashed` leaves the `org.quiltmc:hashed` libraries in, only for debugging remapping.
 #[tracing::instrument(skip_all, err)]
-async fn install_server(install: Install<Version>) -> anyhow::Result<()> {
+pub async fn fetch_server_profile(
+    urls: &BaseUrls,
+    minecraft: &minecraft::Version,
+    version: &Version,
+    keep_hashed: bool,
+) -> anyhow::Result<ServerProfile> {
     // Download server json
-    let mut profile: ServerProfile = reqwest::get(format!(
-        "https://meta.quiltmc.org/v3/versions/loader/{}/{}/server/json",
-        &install.minecraft, &install.version
-    ))
-    .await?
-    .json()
+    let mut profile: ServerProfile = get_profile(
+        format!(
+            "{}/versions/loader/{}/{}/server/json",
+            urls.quilt_meta, minecraft, &version.version
+        ),
+        minecraft,
+        version,
+    )
     .await?;
 
     // Hack-Fix:
     // Quilt-meta specifies both hashed and intermediary, but providing both to quilt-loader causes it to silently fail remapping.
     // This really shouldn't be fixed here in the installer, but we need a solution now.
-    profile
-        .libraries
-        .retain(|lib| !lib.name.starts_with("org.quiltmc:hashed"));
+    if !keep_hashed {
+        profile
+            .libraries
+            .retain(|lib| !lib.name.starts_with("org.quiltmc:hashed"));
+    }
     // End of hack-fix
 
+    Ok(profile)
+}
+
+#[tracing::instrument(skip_all, err)]
+async fn install_server(install: Install<Version>) -> anyhow::Result<InstallReport> {
+    install
+        .progress
+        .report_waiting("Downloading server profile");
+    let profile = fetch_server_profile(
+        &install.urls,
+        &install.minecraft,
+        &install.version,
+        install.keep_hashed_libraries,
+    )
+    .await?;
+
     let libraries_dir = install.dir.to_path_buf().join("libraries");
 
-    let client = reqwest::Client::new();
+    let throttle = install.max_bandwidth.map(Throttle::new);
+
+    // A locked install must download exactly the libraries it recorded
+    let libraries = profile
+        .libraries
+        .iter()
+        .map(|lib| match &install.locked {
+            Some(lock) => lock
+                .library(&lib.name)
+                .cloned()
+                .map(|locked| (lib.clone(), Some(locked)))
+                .with_context(|| format!("Library {} is not in {}", lib.name, lockfile::FILE_NAME)),
+            None => Ok((lib.clone(), None)),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let partial = Partial::open(
+        &install.dir,
+        Side::Server,
+        &install.minecraft,
+        &LoaderVersion::Quilt(install.version.clone()),
+    )
+    .await?;
+
+    if install.preflight {
+        install
+            .progress
+            .report_waiting("Checking libraries can be downloaded");
+        check_available(&install, &libraries_dir, &libraries).await?;
+    }
+
+    let total = libraries.len();
+    let mut done = 0;
 
-    let library_paths = tokio::spawn({
-        let libraries = profile.libraries.clone();
+    let checksums = Checksums::load();
+
+    // Awaited in place rather than spawned, so a timeout or cancelled install drops the
+    // downloads along with it instead of leaving them writing into the directory
+    let downloaded = {
+        let checksums = checksums.clone();
+        let library_cache = install.library_cache.clone();
+        let progress = install.progress.clone();
+        let mirrors = install.mirrors.clone();
+        let cleanup = install.cleanup.clone();
+        let partial = partial.clone();
 
         async move {
-            let library_paths: anyhow::Result<Vec<PathBuf>> = stream::iter(libraries.into_iter())
-                .map(|lib| {
-                    let client = client.clone();
-                    let libraries_dir = libraries_dir.clone();
+            let download = |(lib, locked): (Library, Option<LockedLibrary>)| {
+                let libraries_dir = libraries_dir.clone();
+                let library_cache = library_cache.clone();
+                let throttle = throttle.clone();
+                let mirrors = mirrors.clone();
+                let cleanup = cleanup.clone();
+                let checksums = checksums.clone();
+                let progress = progress.clone();
+                let partial = partial.clone();
+
+                async move {
+                    if let Some(resumed) = resumed(&partial, &libraries_dir, locked.as_ref(), &lib)
+                    {
+                        return (lib, locked, Ok(resumed));
+                    }
+
+                    let result = download_library(
+                        &libraries_dir,
+                        library_cache.as_deref(),
+                        &progress,
+                        throttle.as_ref(),
+                        &mirrors,
+                        &cleanup,
+                        &checksums,
+                        locked.as_ref(),
+                        &lib,
+                    )
+                    .await;
 
-                    async move { download_library(client, &libraries_dir, &lib).await }
+                    if let Ok((_, library)) = &result {
+                        if let Err(err) = partial.record(library).await {
+                            tracing::warn!(library = ?lib.name, error = ?err, "Failed to record finished library");
+                        }
+                    }
+
+                    (lib, locked, result)
+                }
+            };
+
+            // A failed library doesn't stop the others, so a flaky connection only has to
+            // retry the few that failed instead of starting over
+            let results = stream::iter(libraries.into_iter())
+                .map(&download)
+                // Keep the profile's order so the launch jar's Class-Path is stable
+                .buffered(8)
+                .inspect(|(_, _, result)| {
+                    if result.is_ok() {
+                        done += 1;
+                        progress.report(
+                            format!("Downloading libraries ({}/{})", done, total),
+                            0.05 + 0.75 * done as f32 / total as f32,
+                        );
+                    }
                 })
-                .buffer_unordered(8)
-                .try_collect()
+                .collect::<Vec<_>>()
                 .await;
 
-            library_paths
+            let mut downloaded = Vec::with_capacity(total);
+            let mut failed = Vec::new();
+            let mut last_error = None;
+
+            for (lib, locked, result) in results {
+                let result = match result {
+                    Ok(library) => {
+                        downloaded.push(library);
+                        continue;
+                    }
+                    Err(err) => {
+                        tracing::warn!(library = ?lib.name, error = ?err, "Retrying failed library");
+                        progress.report(
+                            format!("Retrying {}", lib.name),
+                            0.05 + 0.75 * done as f32 / total as f32,
+                        );
+
+                        download((lib.clone(), locked)).await.2
+                    }
+                };
+
+                match result {
+                    Ok(library) => {
+                        done += 1;
+                        downloaded.push(library);
+                    }
+                    Err(err) => {
+                        failed.push(lib.name);
+                        last_error = Some(err);
+                    }
+                }
+            }
+
+            match last_error {
+                Some(err) => Err(err.context(format!(
+                    "Failed to download {} of {} libraries: {}",
+                    failed.len(),
+                    total,
+                    failed.join(", ")
+                ))),
+                None => anyhow::Ok(downloaded),
+            }
         }
-    })
-    .await??;
+    }
+    .await?;
+
+    if let Err(err) = checksums.save() {
+        tracing::warn!(error = ?err, "Failed to save checksum cache");
+    }
 
+    let (mut library_paths, locked_libraries): (Vec<PathBuf>, Vec<LockedLibrary>) =
+        downloaded.into_iter().unzip();
+
+    let libraries = locked_libraries
+        .iter()
+        .map(|locked| locked.name.clone())
+        .zip(library_paths.iter().cloned())
+        .collect();
+    let mut files = Vec::new();
+
+    // Extra jars aren't Maven artifacts, so they stay out of the lockfile and library list
+    for path in &install.extra_libs {
+        let path = add_extra_library(&install.dir, path).await?;
+
+        library_paths.push(path.clone());
+        files.push(path);
+    }
+
+    install.progress.report("Writing launch jar", 0.85);
     let jar_path = install.dir.to_path_buf().join("quilt-server-launch.jar");
-    create_launch_jar(&jar_path, &profile.launcher_main_class, &library_paths).await?;
+    create_launch_jar(
+        &jar_path,
+        profile.launch_jar_main_class(),
+        &library_paths,
+        install.jar_compression,
+    )
+    .await?;
+    if install.smoke_test {
+        install.progress.report_waiting("Checking the launch jar");
+        smoke_test(&install.dir, &jar_path, install.java.as_deref()).await?;
+    }
 
-    Ok(())
+    // The Quilt server launcher looks for the vanilla jar as `server.jar` by default
+    if install.download_jar {
+        install.progress.report("Downloading server jar", 0.9);
+        let server_jar = install.dir.join("server.jar");
+        install.cleanup.track(&server_jar);
+        minecraft::download_server_jar(&install.urls, &install.minecraft.version, &server_jar)
+            .await?;
+        install.cleanup.finish(&server_jar);
+        files.push(server_jar);
+    }
+
+    if install.generate_script {
+        install.progress.report("Writing start scripts", 0.95);
+        // Only a runtime that was asked for is written, a found one may not exist where the
+        // server ends up, so the scripts leave it to the `PATH` when they're run
+        let java = install
+            .java
+            .as_deref()
+            .map_or(Cow::Borrowed("java"), Path::to_string_lossy);
+        utils::write_start_scripts(
+            &install.dir,
+            &java,
+            "quilt-server-launch.jar",
+            &profile.arguments.plain_game(),
+        )
+        .await?;
+        files.push(install.dir.join("start.sh"));
+        files.push(install.dir.join("start.bat"));
+    }
+
+    if install.docker {
+        utils::write_docker_files(
+            &install.dir,
+            "quilt-server-launch.jar",
+            &profile.arguments.plain_game(),
+            minecraft::fetch_java_version(&install.urls, &install.minecraft).await,
+        )
+        .await?;
+        files.push(install.dir.join("Dockerfile"));
+        files.push(install.dir.join("docker-compose.yml"));
+    }
+
+    Lockfile {
+        side: Side::Server,
+        minecraft: install.minecraft.clone(),
+        version: LoaderVersion::Quilt(install.version.clone()),
+        libraries: locked_libraries,
+    }
+    .write(&install.dir)
+    .await?;
+    files.push(install.dir.join(lockfile::FILE_NAME));
+    partial.finish().await?;
+
+    Ok(InstallReport {
+        profile_name: None,
+        files,
+        libraries,
+        launch_jar: Some(jar_path),
+        ..Default::default()
+    })
 }
 
+/// Sums up the size of the server libraries that aren't downloaded yet, asking the primary
+/// repository of each with a `HEAD` request.
 #[tracing::instrument(skip_all, err)]
-async fn download_library(
-    client: reqwest::Client,
+pub async fn estimate_download(
+    urls: &BaseUrls,
+    mirrors: &Mirrors,
     dir: &Path,
-    lib: &Library,
-) -> anyhow::Result<PathBuf> {
-    fn split_artifact(artifact_notation: &str) -> Option<String> {
-        let mut parts = artifact_notation.splitn(3, ':');
+    minecraft: &minecraft::Version,
+    version: &Version,
+) -> anyhow::Result<DownloadEstimate> {
+    let profile = fetch_server_profile(urls, minecraft, version, false).await?;
+
+    let libraries_dir = dir.join("libraries");
+    let missing = profile
+        .libraries
+        .iter()
+        .filter_map(library_path)
+        .filter(|path| !libraries_dir.join(path).exists())
+        .filter_map(|path| mirrors.candidates(&path).into_iter().next())
+        .collect::<Vec<_>>();
+
+    let sizes = stream::iter(missing)
+        .map(|url| async move {
+            let res = mirrors
+                .authorize(&url, utils::http::client().head(&url))
+                .send()
+                .await?;
+            if !res.status().is_success() {
+                return anyhow::Ok(None);
+            }
 
-        let group = parts.next()?;
-        let name = parts.next()?;
-        let version = parts.next()?;
+            let size = res
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|length| length.to_str().ok())
+                .and_then(|length| length.parse::<u64>().ok());
 
-        let group = group.replace('.', "/");
+            anyhow::Ok(size)
+        })
+        .buffer_unordered(8)
+        .try_collect::<Vec<_>>()
+        .await?;
 
-        Some(format!(
-            "{}/{}/{}/{}-{}.jar",
-            group, name, version, name, version
-        ))
+    Ok(DownloadEstimate {
+        bytes: sizes.iter().flatten().sum(),
+        approximate: sizes.iter().any(Option::is_none),
+    })
+}
+
+/// Lists the files under `libraries` that the server profile of a version doesn't reference.
+#[tracing::instrument(skip_all, err)]
+pub async fn stale_libraries(
+    urls: &BaseUrls,
+    dir: &Path,
+    minecraft: &minecraft::Version,
+    version: &Version,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let profile = fetch_server_profile(urls, minecraft, version, false).await?;
+
+    let libraries_dir = dir.join("libraries");
+    // Skipping a library here would list its jar as stale, so an unknown name stops the scan
+    let expected = profile
+        .libraries
+        .iter()
+        .map(|lib| {
+            let coord = lib.name.parse::<MavenCoord>()?;
+            anyhow::Ok(libraries_dir.join(coord.to_path()))
+        })
+        .collect::<anyhow::Result<HashSet<_>>>()?;
+
+    let mut stale = Vec::new();
+    let mut pending = vec![libraries_dir];
+
+    while let Some(dir) = pending.pop() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                pending.push(entry.path());
+            } else if !expected.contains(&entry.path()) {
+                stale.push(entry.path());
+            }
+        }
     }
 
-    let raw_path =
-        split_artifact(&lib.name).context("Failed to build maven artifact from library name")?;
-    let maven_url = match () {
-        _ if raw_path.starts_with("org/quiltmc") => {
-            format!("{}/{}", MAVEN, raw_path)
+    stale.sort();
+
+    Ok(stale)
+}
+
+/// Fails listing every library that none of its repositories have, before any is downloaded.
+///
+/// Libraries already in the install or the library cache aren't checked, locked ones are checked
+/// at their recorded URL first like they're downloaded.
+#[tracing::instrument(skip_all, err)]
+async fn check_available(
+    install: &Install<Version>,
+    libraries_dir: &Path,
+    libraries: &[(Library, Option<LockedLibrary>)],
+) -> anyhow::Result<()> {
+    let mirrors = &install.mirrors;
+
+    let mut pending = Vec::new();
+    for (lib, locked) in libraries {
+        let raw_path = lib.name.parse::<MavenCoord>()?.to_path();
+
+        let cached = install
+            .library_cache
+            .as_ref()
+            .map_or(false, |cache| cache.join(&raw_path).exists());
+        if cached || libraries_dir.join(&raw_path).exists() {
+            continue;
         }
-        _ => {
-            format!("{}/{}", fabric::MAVEN, raw_path)
+
+        let mut urls = mirrors.candidates(&raw_path);
+        if let Some(locked) = locked {
+            urls.retain(|url| *url != locked.url);
+            urls.insert(0, locked.url.clone());
         }
-    };
+
+        pending.push((lib.name.as_str(), urls));
+    }
+
+    let missing = stream::iter(pending)
+        .map(|(name, urls)| async move {
+            for url in &urls {
+                let res = mirrors
+                    .authorize(url, utils::http::client().head(url))
+                    .send()
+                    .await;
+
+                match res {
+                    Ok(res) if res.status().is_success() => return None,
+                    Ok(res) => {
+                        tracing::debug!(url = ?url, status = ?res.status(), "Library not available")
+                    }
+                    Err(err) => tracing::debug!(url = ?url, error = ?err, "Library not reachable"),
+                }
+            }
+
+            Some(name)
+        })
+        .buffered(8)
+        .filter_map(future::ready)
+        .collect::<Vec<_>>()
+        .await;
+
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "{} of {} libraries can't be downloaded from any repository: {}",
+            missing.len(),
+            libraries.len(),
+            missing.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// The repository path of a library, names that aren't Maven coordinates are logged and skipped.
+fn library_path(lib: &Library) -> Option<String> {
+    match lib.name.parse::<MavenCoord>() {
+        Ok(coord) => Some(coord.to_path()),
+        Err(err) => {
+            tracing::warn!(
+                library = ?lib.name,
+                error = ?err,
+                "Skipping library with an unsupported name"
+            );
+            None
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip_all, err)]
+async fn download_library(
+    dir: &Path,
+    cache: Option<&Path>,
+    progress: &Reporter,
+    throttle: Option<&Throttle>,
+    mirrors: &Mirrors,
+    cleanup: &Cleanup,
+    checksums: &Checksums,
+    locked: Option<&LockedLibrary>,
+    lib: &Library,
+) -> anyhow::Result<(PathBuf, LockedLibrary)> {
+    let raw_path = lib.name.parse::<MavenCoord>()?.to_path();
+
+    // A locked URL goes first, the mirrors can still step in if it has gone away since
+    let mut urls = mirrors.candidates(&raw_path);
+    if let Some(locked) = locked {
+        urls.retain(|url| *url != locked.url);
+        urls.insert(0, locked.url.clone());
+    }
+    let maven_url = urls[0].clone();
 
     let path = dir.join(PathBuf::from(&raw_path));
 
+    let downloaded =
+        fetch_library(cache, throttle, mirrors, cleanup, &raw_path, &urls, &path).await?;
+    progress.add_downloaded(downloaded);
+
+    let sha256 = checksums.sha256(&path).await?;
+    if let Some(locked) = locked {
+        if locked.sha256 != sha256 {
+            return Err(lockfile::ChecksumMismatch {
+                name: lib.name.clone(),
+                expected: locked.sha256.clone(),
+                actual: sha256,
+            }
+            .into());
+        }
+    }
+
+    Ok((
+        path,
+        LockedLibrary {
+            name: lib.name.clone(),
+            url: maven_url,
+            sha256,
+        },
+    ))
+}
+
+/// A library an earlier run of the same install finished, skipped without hashing it again as
+/// long as its file is still there and it's what a locked install expects.
+fn resumed(
+    partial: &Partial,
+    dir: &Path,
+    locked: Option<&LockedLibrary>,
+    lib: &Library,
+) -> Option<(PathBuf, LockedLibrary)> {
+    let done = partial.library(&lib.name)?;
+    if locked.map_or(false, |locked| locked.sha256 != done.sha256) {
+        return None;
+    }
+
+    let path = dir.join(lib.name.parse::<MavenCoord>().ok()?.to_path());
+    path.exists().then(|| (path, done.clone()))
+}
+
+/// Puts a library at `path` from the cache or the first repository that has it, returning how
+/// many bytes had to be downloaded.
+async fn fetch_library(
+    cache: Option<&Path>,
+    throttle: Option<&Throttle>,
+    mirrors: &Mirrors,
+    cleanup: &Cleanup,
+    raw_path: &str,
+    urls: &[String],
+    path: &Path,
+) -> anyhow::Result<u64> {
     if path.exists() {
         tracing::info!(library = ?raw_path, "Library already downloaded, skipping...");
-        return Ok(path);
+        return Ok(0);
     }
 
     let parent = path
@@ -259,30 +1002,185 @@ async fn download_library(
         .expect("Install dir library has no parent folder");
     tokio::fs::create_dir_all(parent).await?;
 
+    if let Some(cached) = cache.map(|cache| cache.join(raw_path)) {
+        if cached.exists() {
+            // Libraries never change once written, so both installs can share the same file
+            if tokio::fs::hard_link(&cached, path).await.is_ok() {
+                tracing::info!(library = ?raw_path, "Linked library from cache");
+                return Ok(0);
+            }
+
+            tracing::info!(library = ?raw_path, "Copying library from cache");
+            tokio::fs::copy(&cached, path).await?;
+            return Ok(0);
+        }
+    }
+
     tracing::info!(library = ?raw_path, "Downloading library");
+    cleanup.track(path);
+
+    let mut last_error = None;
+    for url in urls {
+        match download_to(url, mirrors, throttle, path).await {
+            Ok(bytes) => {
+                tracing::info!(library = ?raw_path, url = ?url, bytes, "Downloaded library");
+                cleanup.finish(path);
+                return Ok(bytes);
+            }
+            Err(err) => {
+                tracing::warn!(library = ?raw_path, url = ?url, error = ?err, "Library download failed");
 
-    let res = client.get(maven_url).send().await?;
-    if !res.status().is_success() {
-        anyhow::bail!(
-            "Library download returned with status code: {}",
-            res.status()
-        );
+                // Don't leave a partial file behind that later runs would take as downloaded
+                let _ = tokio::fs::remove_file(path).await;
+                last_error = Some(err);
+            }
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| anyhow::anyhow!("No repository to download {} from", raw_path)))
+}
+
+async fn download_to(
+    url: &str,
+    mirrors: &Mirrors,
+    throttle: Option<&Throttle>,
+    path: &Path,
+) -> anyhow::Result<u64> {
+    let res = mirrors
+        .authorize(url, utils::http::client().get(url))
+        .send()
+        .await?
+        .error_for_status()
+        .context("Library download failed")?;
+
+    if res.content_length() == Some(0) {
+        anyhow::bail!("{} returned an empty file", url);
+    }
+
+    let content_type = res
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let mut written = 0;
+    match throttle {
+        Some(throttle) => {
+            // Held back until there's enough to tell whether it's a jar at all
+            let mut head = Vec::new();
+            let mut file = None;
+
+            let mut chunks = res.bytes_stream();
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk?;
+
+                throttle.consume(chunk.len()).await;
+                written += chunk.len() as u64;
+                match &mut file {
+                    Some(file) => file.write_all(&chunk).await?,
+                    None => {
+                        head.extend_from_slice(&chunk);
+                        if head.len() >= ZIP_MAGIC.len() {
+                            check_jar(url, &content_type, &head)?;
+
+                            let mut created = File::create(path).await?;
+                            created.write_all(&head).await?;
+                            file = Some(created);
+                        }
+                    }
+                }
+            }
+
+            match file {
+                Some(mut file) => file.flush().await?,
+                None => check_jar(url, &content_type, &head)?,
+            }
+        }
+        None => {
+            let bytes = res.bytes().await?;
+            check_jar(url, &content_type, &bytes)?;
+
+            tokio::fs::write(path, &bytes[..]).await?;
+            written = bytes.len() as u64;
+        }
+    }
+
+    Ok(written)
+}
+
+/// Every jar is a zip, and every non-empty zip starts with a local file header.
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Fails on a download that can't be a jar, like the HTML error page of a misconfigured mirror
+/// served with a success status.
+fn check_jar(url: &str, content_type: &str, bytes: &[u8]) -> anyhow::Result<()> {
+    if bytes.is_empty() {
+        anyhow::bail!("{} returned an empty file", url);
+    }
+    if !bytes.starts_with(ZIP_MAGIC) {
+        anyhow::bail!("{} didn't return a jar but {}", url, content_type);
+    }
+
+    Ok(())
+}
+
+/// Makes a user supplied jar available to the launch jar, returning where it ended up.
+///
+/// Jars already inside the install directory are referenced in place, anything else is copied
+/// into `libraries/extra` so the server directory can be moved around as a whole.
+async fn add_extra_library(dir: &Path, path: &Path) -> anyhow::Result<PathBuf> {
+    let is_jar = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("jar"))
+        .unwrap_or(false);
+    if !is_jar {
+        anyhow::bail!("Extra library is not a .jar: {}", path.display());
+    }
+
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read extra library: {}", path.display()))?;
+    zip::ZipArchive::new(Cursor::new(bytes))
+        .with_context(|| format!("Extra library is not a valid jar: {}", path.display()))?;
+
+    let path = tokio::fs::canonicalize(path).await?;
+    let dir = tokio::fs::canonicalize(dir).await?;
+    if path.starts_with(&dir) {
+        return Ok(path);
     }
 
-    let bytes = res.bytes().await?;
-    tokio::fs::write(&path, &bytes[..]).await?;
+    let file_name = path.file_name().context("Extra library has no file name")?;
+    let target = dir.join("libraries").join("extra").join(file_name);
+
+    tokio::fs::create_dir_all(target.parent().expect("Extra library path has no parent")).await?;
+    tokio::fs::copy(&path, &target)
+        .await
+        .with_context(|| format!("Failed to copy extra library: {}", path.display()))?;
 
-    Ok(path)
+    Ok(target)
 }
 
+/// Creates a manifest only jar that puts the libraries on the Class-Path.
+///
+/// The output is byte-identical for the same libraries, entries get a fixed modification time
+/// instead of the current one.
 #[tracing::instrument(skip_all, err)]
-async fn create_launch_jar(jar: &Path, main: &str, libraries: &[PathBuf]) -> anyhow::Result<()> {
+async fn create_launch_jar(
+    jar: &Path,
+    main: &str,
+    libraries: &[PathBuf],
+    compression: JarCompression,
+) -> anyhow::Result<()> {
     tracing::info!("Creating server launch jar");
 
     let buf = Cursor::new(Vec::with_capacity(1024 * 2));
     let mut archive = zip::ZipWriter::new(buf);
 
-    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let options = FileOptions::default()
+        .compression_method(compression.into())
+        .last_modified_time(DateTime::default());
 
     let parent = jar
         .parent()
@@ -292,25 +1190,13 @@ async fn create_launch_jar(jar: &Path, main: &str, libraries: &[PathBuf]) -> any
 
     let mut manifest = Vec::new();
     writeln!(&mut manifest, "Manifest-Version: 1.0")?;
-    writeln!(&mut manifest, "Main-Class: {}", main)?;
+    write_manifest_header(&mut manifest, &format!("Main-Class: {}", main))?;
 
-    let relative_paths = libraries
+    let entries = libraries
         .iter()
-        .map(|path| {
-            path.strip_prefix(parent)
-                .context("Failed to make library path relative to install directory")
-                .map(|path| path.display().to_string().replace('\\', "/"))
-        })
-        .collect::<anyhow::Result<Vec<String>>>()?;
-
-    let class_path = format!("Class-Path: {}", relative_paths.join(" "));
-
-    let (head, tail) = class_path.split_at(72);
-    writeln!(&mut manifest, "{}", &head)?;
-
-    for chunk in tail.as_bytes().chunks(71) {
-        writeln!(&mut manifest, " {}", String::from_utf8_lossy(chunk))?;
-    }
+        .map(|path| class_path_entry(parent, path))
+        .collect::<Vec<String>>();
+    write_manifest_header(&mut manifest, &format!("Class-Path: {}", entries.join(" ")))?;
 
     archive.write_all(&manifest)?;
 
@@ -321,6 +1207,352 @@ async fn create_launch_jar(jar: &Path, main: &str, libraries: &[PathBuf]) -> any
     Ok(())
 }
 
+/// The longest a manifest line may be in bytes, not counting the line break.
+const MANIFEST_LINE_LENGTH: usize = 72;
+
+/// Writes a manifest header, continued on lines starting with a space so none of them is longer
+/// than the jar spec allows, without splitting a character across lines.
+fn write_manifest_header(manifest: &mut Vec<u8>, header: &str) -> std::io::Result<()> {
+    let mut line = String::with_capacity(MANIFEST_LINE_LENGTH);
+    for c in header.chars() {
+        if line.len() + c.len_utf8() > MANIFEST_LINE_LENGTH {
+            writeln!(manifest, "{}", line)?;
+            line.clear();
+            line.push(' ');
+        }
+        line.push(c);
+    }
+
+    writeln!(manifest, "{}", line)
+}
+
+/// How long the JVM gets to load the launch jar's main class.
+const SMOKE_TEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Has Java load the launch jar's main class without running it, which only works if the
+/// manifest and every Class-Path entry it needs on the way are readable.
+///
+/// `--dry-run` was added in Java 9, older runtimes are reported instead of guessed around.
+#[tracing::instrument(skip_all, err)]
+async fn smoke_test(dir: &Path, jar: &Path, java: Option<&Path>) -> anyhow::Result<()> {
+    let java = utils::java::find_or(java)
+        .await
+        .context("The launch jar smoke test needs Java")?
+        .path;
+
+    tracing::info!(java = ?java, jar = ?jar, "Loading the launch jar's main class");
+
+    let output = tokio::process::Command::new(&java)
+        .arg("--dry-run")
+        .arg("-jar")
+        .arg(jar)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(SMOKE_TEST_TIMEOUT, output).await {
+        Ok(output) => output.context("Failed to run Java for the smoke test")?,
+        Err(_) => anyhow::bail!(
+            "Smoke test timed out after {} seconds",
+            SMOKE_TEST_TIMEOUT.as_secs()
+        ),
+    };
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("Unrecognized option: --dry-run") {
+        anyhow::bail!(
+            "Smoke test needs Java 9 or newer, {} is older",
+            java.display()
+        );
+    }
+
+    let errors = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+
+    anyhow::bail!(
+        "Launch jar smoke test failed ({}): {}",
+        output.status,
+        if errors.is_empty() {
+            "no output".to_string()
+        } else {
+            errors.join("; ")
+        }
+    )
+}
+
+/// Builds the Class-Path entry for a library, relative to the launch jar's directory.
+///
+/// A library on another Windows drive can't be reached relatively, it gets an absolute `file:`
+/// URL instead. Entries are URLs separated by spaces, so everything but the characters URLs
+/// allow as is gets percent-encoded, spaces and non-ASCII names included.
+fn class_path_entry(base: &Path, library: &Path) -> String {
+    match relative_path(base, library) {
+        Some(relative) => {
+            let relative = relative
+                .iter()
+                .map(|component| component.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            // A colon in a relative entry would make everything before it read as a scheme
+            percent_encode(&relative, false)
+        }
+        None => {
+            let absolute = percent_encode(&library.display().to_string().replace('\\', "/"), true);
+
+            if absolute.starts_with('/') {
+                format!("file://{}", absolute)
+            } else {
+                format!("file:///{}", absolute)
+            }
+        }
+    }
+}
+
+/// Encodes every byte of `path` outside the unreserved URL characters and `/`, keeping `:` for
+/// the drive of absolute Windows paths.
+fn percent_encode(path: &str, keep_colon: bool) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            b':' if keep_colon => encoded.push(':'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Reverses [`percent_encode`], leaving anything that isn't a valid escape as it is.
+fn percent_decode(entry: &str) -> String {
+    let bytes = entry.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Turns a Class-Path entry written by [`class_path_entry`] back into a path.
+fn class_path_entry_path(base: &Path, entry: &str) -> PathBuf {
+    let entry = percent_decode(entry);
+
+    match entry.strip_prefix("file://") {
+        // `file:///C:/...` on Windows, `file:///...` everywhere else
+        Some(path) if path.get(2..3) == Some(":") => PathBuf::from(&path[1..]),
+        Some(path) => PathBuf::from(path),
+        None => base.join(entry),
+    }
+}
+
+/// Computes `path` relative to `base`, stepping out of `base` with `..` where needed.
+///
+/// Returns `None` if the two don't share a root, like paths on different Windows drives.
+fn relative_path(base: &Path, path: &Path) -> Option<PathBuf> {
+    let base = base.components().collect::<Vec<_>>();
+    let path = path.components().collect::<Vec<_>>();
+
+    let is_root = |component: Option<&Component>| {
+        matches!(component, Some(Component::Prefix(_) | Component::RootDir))
+    };
+    if base.first() != path.first() && (is_root(base.first()) || is_root(path.first())) {
+        return None;
+    }
+
+    let common = base
+        .iter()
+        .zip(&path)
+        .take_while(|(base, path)| base == path)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+
+    Some(relative)
+}
+
+/// Checks an existing install without downloading anything, returning every problem found.
+///
+/// `versions` are the Minecraft and loader version, only client installs are found by them.
+#[tracing::instrument(skip_all, err)]
+pub async fn verify(
+    side: Side,
+    dir: &Path,
+    versions: Option<(&str, &str)>,
+) -> anyhow::Result<Vec<String>> {
+    match (side, versions) {
+        (Side::Client, Some((minecraft, version))) => verify_client(dir, minecraft, version).await,
+        (Side::Client, None) => {
+            anyhow::bail!("Verifying a client install needs its Minecraft and loader version")
+        }
+        (Side::Server, _) => verify_server(dir).await,
+    }
+}
+
+async fn verify_client(dir: &Path, minecraft: &str, version: &str) -> anyhow::Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    let profile_name = format!("quilt-loader-{}-{}", version, minecraft);
+    let mut profile_dir = dir.to_path_buf();
+    profile_dir.push("versions");
+    profile_dir.push(&profile_name);
+
+    // Installs for launchers that don't need the jar skip it
+    let jar_path = profile_dir.join(format!("{}.jar", &profile_name));
+    if !jar_path.exists() {
+        tracing::info!(jar = ?jar_path, "No profile jar, the install may have skipped it");
+    }
+
+    let json_path = profile_dir.join(format!("{}.json", &profile_name));
+    if !json_path.exists() {
+        problems.push(format!("Missing profile json: {}", json_path.display()));
+        return Ok(problems);
+    }
+
+    let profile = tokio::fs::read_to_string(&json_path).await?;
+    let profile: ClientProfile = match serde_json::from_str(&profile) {
+        Ok(profile) => profile,
+        Err(err) => {
+            problems.push(format!(
+                "Corrupt profile json {}: {}",
+                json_path.display(),
+                err
+            ));
+            return Ok(problems);
+        }
+    };
+
+    // The launcher downloads the libraries on the first start, a fresh install has none of them
+    let libraries_dir = dir.join("libraries");
+    for lib in &profile.libraries {
+        match lib.name.parse::<MavenCoord>() {
+            Ok(coord) if libraries_dir.join(coord.to_path()).exists() => {}
+            Ok(_) => {
+                tracing::info!(library = ?lib.name, "Library not downloaded by the launcher yet")
+            }
+            Err(err) => problems.push(format!("{:#}", err)),
+        }
+    }
+
+    Ok(problems)
+}
+
+async fn verify_server(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    let jar_path = dir.join("quilt-server-launch.jar");
+    if !jar_path.exists() {
+        problems.push(format!("Missing launch jar: {}", jar_path.display()));
+        return Ok(problems);
+    }
+
+    let bytes = tokio::fs::read(&jar_path).await?;
+    let manifest = zip::ZipArchive::new(Cursor::new(bytes)).and_then(|mut archive| {
+        let mut manifest = String::new();
+        archive
+            .by_name("META-INF/MANIFEST.MF")?
+            .read_to_string(&mut manifest)?;
+
+        Ok(manifest)
+    });
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            problems.push(format!(
+                "Corrupt launch jar {}: {}",
+                jar_path.display(),
+                err
+            ));
+            return Ok(problems);
+        }
+    };
+
+    // Manifest lines are wrapped at 72 bytes, continuations start with a single space
+    let mut lines: Vec<String> = Vec::new();
+    for line in manifest.lines() {
+        match (line.strip_prefix(' '), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let class_path = lines
+        .iter()
+        .find_map(|line| line.strip_prefix("Class-Path: "))
+        .unwrap_or_default();
+
+    for entry in class_path.split_whitespace() {
+        let path = class_path_entry_path(dir, entry);
+        if !path.exists() {
+            problems.push(format!("Missing Class-Path entry: {}", path.display()));
+        }
+    }
+
+    // Without a lockfile there is nothing to compare the libraries against
+    if dir.join(lockfile::FILE_NAME).exists() {
+        let lock = Lockfile::read(dir).await?;
+        let checksums = Checksums::load();
+
+        for lib in &lock.libraries {
+            let path = match lib.name.parse::<MavenCoord>() {
+                Ok(coord) => dir.join("libraries").join(coord.to_path()),
+                Err(_) => continue,
+            };
+            if !path.exists() {
+                continue;
+            }
+
+            if checksums.sha256(&path).await? != lib.sha256 {
+                problems.push(format!(
+                    "Library {} doesn't match {}: {}",
+                    lib.name,
+                    lockfile::FILE_NAME,
+                    path.display()
+                ));
+            }
+        }
+
+        if let Err(err) = checksums.save() {
+            tracing::warn!(error = ?err, "Failed to save checksum cache");
+        }
+    }
+
+    Ok(problems)
+}
+
 #[derive(Debug)]
 pub enum Message {
     Error(anyhow::Error),
@@ -329,6 +1561,7 @@ pub enum Message {
 
     SetMinecraft(anyhow::Result<Vec<minecraft::Version>>),
     SetVersions(anyhow::Result<Vec<Version>>),
+    SetSupported(String, anyhow::Result<Vec<String>>),
 }
 
 #[allow(clippy::from_over_into)]
@@ -344,6 +1577,9 @@ pub enum Interaction {
 
     SelectVersion(Version),
     ShowBetas(bool),
+    Autocomplete(autocomplete::Event),
+
+    Refresh,
 }
 
 #[derive(Debug, Default)]
@@ -354,9 +1590,26 @@ pub struct State {
     pub versions: Vec<Version>,
     pub selected_version: Option<Version>,
     pub show_betas: bool,
+    pub autocomplete: Autocomplete,
+    /// Loader versions supporting each snapshot looked up so far, not every build does.
+    pub supported: HashMap<String, Vec<String>>,
+
+    /// Why the version lists are empty, shown next to a refresh button.
+    pub status: Option<String>,
+    pub refresh_button: button::State,
 }
 
 impl State {
+    pub fn fetch(urls: &BaseUrls) -> Command<Message> {
+        Command::batch([
+            Command::perform(
+                Commands::fetch_minecraft(urls.clone()),
+                Message::SetMinecraft,
+            ),
+            Command::perform(Commands::fetch_versions(urls.clone()), Message::SetVersions),
+        ])
+    }
+
     pub fn selected_version(&self) -> Option<Version> {
         self.selected_version.as_ref().cloned()
     }
@@ -365,17 +1618,148 @@ impl State {
         self.minecraft.selected_version.as_ref().cloned()
     }
 
+    pub fn set_show_unstable(&mut self, show: bool) {
+        self.minecraft.set_show_unstable(show);
+        self.show_betas = show;
+    }
+
+    /// Selects the versions of an earlier install, `false` if either isn't listed anymore.
+    pub fn select_existing(&mut self, version: &str, minecraft: &str) -> bool {
+        let version = self.versions.iter().find(|v| v.version == version);
+        let minecraft = self
+            .minecraft
+            .versions
+            .iter()
+            .find(|v| v.version == minecraft);
+
+        match (version, minecraft) {
+            (Some(version), Some(minecraft)) => {
+                self.selected_version = Some(version.clone());
+                self.minecraft.selected_version = Some(minecraft.clone());
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Looks up which loader versions support the selected Minecraft version, only needed for
+    /// snapshots as every build supports the releases meta lists.
+    pub fn fetch_supported(&self, urls: &BaseUrls) -> Command<Message> {
+        match &self.minecraft.selected_version {
+            Some(selected)
+                if selected.kind() != minecraft::VersionType::Release
+                    && !self.supported.contains_key(&selected.version) =>
+            {
+                let version = selected.version.clone();
+
+                Command::perform(
+                    Commands::fetch_supported(urls.clone(), version.clone()),
+                    move |result| Message::SetSupported(version, result),
+                )
+            }
+            _ => Command::none(),
+        }
+    }
+
+    /// The loader versions the selected snapshot is limited to, `None` for releases and while
+    /// they're still being looked up.
+    fn supported_versions(&self) -> Option<&[String]> {
+        let minecraft = self.minecraft.selected_version.as_ref()?;
+
+        self.supported.get(&minecraft.version).map(Vec::as_slice)
+    }
+
+    /// The loader versions on offer, leaving out betas unless shown and builds the selected
+    /// snapshot isn't supported by.
+    fn offered_versions(&self) -> Vec<Version> {
+        let supported = self.supported_versions();
+
+        self.versions
+            .iter()
+            .filter(|v| self.show_betas || !v.version.contains("beta"))
+            .filter(|v| supported.map_or(true, |supported| supported.contains(&v.version)))
+            .cloned()
+            .collect()
+    }
+
+    /// Moves the highlight of whichever version input has the keyboard focus.
+    pub fn move_highlight(&mut self, down: bool) {
+        self.minecraft.move_highlight(down);
+
+        let versions = self.offered_versions();
+        self.autocomplete.move_highlight(down, &versions);
+    }
+
+    fn recommended_version(&self) -> Option<Version> {
+        self.versions
+            .iter()
+            .find(|v| v.recommended)
+            .or_else(|| self.versions.iter().find(|v| !v.version.contains("beta")))
+            .cloned()
+    }
+
+    /// Goes back to the recommended build on a stable Minecraft version, for simple mode.
+    pub fn select_recommended(&mut self) {
+        self.minecraft.select_allowed();
+        self.selected_version = self.recommended_version();
+        self.select_supported();
+    }
+
+    /// Moves the selection to a supported loader version if the selected one isn't.
+    fn select_supported(&mut self) {
+        // Borrowing the fields directly leaves the selection free to change
+        let supported = self
+            .minecraft
+            .selected_version
+            .as_ref()
+            .and_then(|minecraft| self.supported.get(&minecraft.version));
+        let supported = match supported {
+            Some(supported) => supported,
+            None => return,
+        };
+
+        let selected = self.selected_version.as_ref();
+        if selected.map_or(true, |v| !supported.contains(&v.version)) {
+            self.selected_version = self
+                .versions
+                .iter()
+                .find(|v| supported.contains(&v.version))
+                .cloned();
+        }
+    }
+
+    pub fn carry_over_minecraft(
+        &mut self,
+        versions: &[minecraft::Version],
+        selected: Option<&minecraft::Version>,
+    ) {
+        self.minecraft.carry_over(versions, selected);
+    }
+
     pub fn update_interaction(&mut self, interaction: Interaction) -> Command<Message> {
         match interaction {
             Interaction::Minecraft(interaction) => {
-                return self
+                let command = self
                     .minecraft
                     .update_interaction(interaction)
-                    .map(Message::Minecraft)
+                    .map(Message::Minecraft);
+                self.select_supported();
+
+                return command;
             }
 
             Interaction::SelectVersion(version) => self.selected_version = Some(version),
             Interaction::ShowBetas(show) => self.show_betas = show,
+            Interaction::Autocomplete(event) => {
+                let versions = self.offered_versions();
+                if let Some(version) = self.autocomplete.update(event, &versions) {
+                    self.selected_version = Some(version);
+                }
+            }
+
+            // The GUI does the fetching as it knows where from
+            Interaction::Refresh => self.status = None,
         }
 
         Command::none()
@@ -394,8 +1778,19 @@ impl State {
 
             Message::SetMinecraft(result) => {
                 match result {
+                    Ok(versions) if versions.is_empty() => {
+                        self.status = Some(
+                            "No Minecraft versions available from Quilt meta, try again later"
+                                .to_string(),
+                        );
+                    }
                     Ok(versions) => self.minecraft.versions = versions,
-                    Err(error) => return Message::Error(error).into(),
+                    Err(error) => {
+                        self.status =
+                            Some(format!("Failed to fetch Minecraft versions: {}", error));
+
+                        return Message::Error(error).into();
+                    }
                 }
 
                 if self.minecraft.selected_version.is_none() {
@@ -405,38 +1800,79 @@ impl State {
             }
             Message::SetVersions(result) => {
                 match result {
+                    Ok(versions) if versions.is_empty() => {
+                        self.status = Some(
+                            "No loader versions available from Quilt meta, try again later"
+                                .to_string(),
+                        );
+                    }
                     Ok(versions) => self.versions = versions,
-                    Err(error) => return Message::Error(error).into(),
+                    Err(error) => {
+                        self.status = Some(format!("Failed to fetch loader versions: {}", error));
+
+                        return Message::Error(error).into();
+                    }
                 }
 
                 if self.selected_version.is_none() {
-                    self.selected_version = self
-                        .versions
-                        .iter()
-                        .find(|v| !v.version.contains("beta"))
-                        .cloned();
+                    self.selected_version = self.recommended_version();
                 }
             }
+            Message::SetSupported(minecraft, result) => match result {
+                Ok(versions) => {
+                    self.supported.insert(minecraft, versions);
+                    self.select_supported();
+                }
+                // Every build stays on offer, the install fails with a clear error if need be
+                Err(error) => return Message::Error(error).into(),
+            },
         }
 
         Command::none()
     }
 
-    pub fn view(&mut self) -> Element<'_, Interaction> {
-        Column::new()
-            .push(self.minecraft.view().map(Interaction::Minecraft))
-            .push(
+    /// The text inputs `view` shows, in the order it lays them out.
+    pub fn text_inputs(&mut self, expert: bool) -> Vec<&mut text_input::State> {
+        let mut inputs = self.minecraft.text_inputs(expert);
+        if expert {
+            inputs.push(self.autocomplete.input());
+        }
+
+        inputs
+    }
+
+    /// Simple mode names the build that gets installed instead of offering every build.
+    pub fn view(&mut self, expert: bool) -> Element<'_, Interaction> {
+        let supported = self.supported_versions().map(<[String]>::to_vec);
+        let versions = self.offered_versions();
+
+        let column = Column::new().push(self.minecraft.view(expert).map(Interaction::Minecraft));
+        let column = if !expert {
+            let selected = match &self.selected_version {
+                Some(version) => version.to_string(),
+                None => tr("no_version_yet").to_string(),
+            };
+
+            column.push(
+                Row::new()
+                    .push(Text::new(tr("loader_version")).width(Length::Units(140)))
+                    .push(Text::new(selected))
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center)
+                    .spacing(5)
+                    .padding(5),
+            )
+        } else {
+            let (input, matches) = self.autocomplete.view(&versions, Interaction::Autocomplete);
+
+            let column = column.push(
                 Row::new()
-                    .push(Text::new("Loader version:").width(Length::Units(140)))
+                    .push(Text::new(tr("loader_version")).width(Length::Units(140)))
+                    .push(input)
                     .push(
                         PickList::new(
                             &mut self.pick_list,
-                            Cow::from_iter(
-                                self.versions
-                                    .iter()
-                                    .filter(|v| self.show_betas || !v.version.contains("beta"))
-                                    .cloned(),
-                            ),
+                            Cow::from(versions),
                             self.selected_version.clone(),
                             Interaction::SelectVersion,
                         )
@@ -444,14 +1880,504 @@ impl State {
                     )
                     .push(Checkbox::new(
                         self.show_betas,
-                        "Show betas",
+                        tr("show_betas"),
                         Interaction::ShowBetas,
                     ))
                     .width(Length::Fill)
                     .align_items(Alignment::Center)
                     .spacing(5)
                     .padding(5),
-            )
-            .into()
+            );
+            match matches {
+                Some(matches) => column.push(autocomplete::indent(matches)),
+                None => column,
+            }
+        };
+
+        // Loaders take a while to support a new snapshot
+        let column = match &supported {
+            Some(supported) if supported.is_empty() => column.push(
+                Row::new()
+                    .push(Text::new(tr("no_loader_for_snapshot")))
+                    .padding(5),
+            ),
+            _ => column,
+        };
+
+        match &self.status {
+            Some(status) => column
+                .push(
+                    Row::new()
+                        .push(Text::new(status.as_str()).width(Length::Fill))
+                        .push(
+                            Button::new(&mut self.refresh_button, Text::new(tr("refresh")))
+                                .on_press(Interaction::Refresh),
+                        )
+                        .align_items(Alignment::Center)
+                        .spacing(5)
+                        .padding(5),
+                )
+                .into(),
+            None => column.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://maven.example.com/lib.jar";
+
+    #[test]
+    fn check_jar_rejects_html() {
+        let body = b"<!DOCTYPE html><html><body>Not Found</body></html>";
+
+        let err = check_jar(URL, "text/html", body).unwrap_err();
+        assert!(err.to_string().contains("text/html"));
+    }
+
+    #[test]
+    fn check_jar_rejects_empty_body() {
+        let err = check_jar(URL, "application/java-archive", b"").unwrap_err();
+        assert!(err.to_string().contains("empty"));
+    }
+
+    #[test]
+    fn check_jar_accepts_zip() {
+        check_jar(
+            URL,
+            "application/java-archive",
+            b"PK\x03\x04rest of the jar",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn relative_path_nested() {
+        let relative = relative_path(
+            Path::new("/srv/server"),
+            Path::new("/srv/server/libraries/org/lib.jar"),
+        );
+
+        assert_eq!(relative, Some(PathBuf::from("libraries/org/lib.jar")));
+    }
+
+    #[test]
+    fn relative_path_sibling() {
+        let relative = relative_path(
+            Path::new("/srv/server"),
+            Path::new("/srv/libraries/lib.jar"),
+        );
+
+        assert_eq!(relative, Some(PathBuf::from("../libraries/lib.jar")));
+    }
+
+    #[test]
+    fn relative_path_without_common_root() {
+        assert_eq!(
+            relative_path(Path::new("server"), Path::new("/srv/lib.jar")),
+            None
+        );
+    }
+
+    #[test]
+    fn class_path_entry_nested_and_sibling() {
+        let base = Path::new("/srv/server");
+
+        assert_eq!(
+            class_path_entry(base, Path::new("/srv/server/libraries/org/lib.jar")),
+            "libraries/org/lib.jar"
+        );
+        assert_eq!(
+            class_path_entry(base, Path::new("/srv/shared/libraries/lib.jar")),
+            "../shared/libraries/lib.jar"
+        );
+    }
+
+    #[test]
+    fn class_path_entry_encodes_spaces_and_non_ascii() {
+        let entry = class_path_entry(
+            Path::new("/srv/server"),
+            Path::new("/srv/server/libraries/my libs/ünï.jar"),
+        );
+
+        assert_eq!(entry, "libraries/my%20libs/%C3%BCn%C3%AF.jar");
+    }
+
+    #[test]
+    fn class_path_entry_round_trip() {
+        let base = Path::new("/srv/server");
+        let library = Path::new("/srv/server/libraries/my libs/ünï 100%.jar");
+
+        let entry = class_path_entry(base, library);
+        assert!(!entry.contains(' '));
+        assert_eq!(class_path_entry_path(base, &entry), library);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn class_path_entry_absolute_round_trip() {
+        let base = Path::new("server");
+        let library = Path::new("/srv/libraries/a b.jar");
+
+        let entry = class_path_entry(base, library);
+        assert_eq!(entry, "file:///srv/libraries/a%20b.jar");
+        assert_eq!(class_path_entry_path(base, &entry), library);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn class_path_entry_cross_drive() {
+        let base = Path::new(r"C:\server");
+        let library = Path::new(r"D:\libraries\a b.jar");
+
+        assert_eq!(relative_path(base, library), None);
+
+        let entry = class_path_entry(base, library);
+        assert_eq!(entry, "file:///D:/libraries/a%20b.jar");
+        assert_eq!(
+            class_path_entry_path(base, &entry),
+            PathBuf::from("D:/libraries/a b.jar")
+        );
+    }
+
+    const MAIN: &str = "org.quiltmc.loader.impl.launch.server.QuiltServerLauncher";
+
+    /// A fresh directory for one test, as the tests of a run share the temp directory.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "anymc-installer-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[tokio::test]
+    async fn launch_jar_is_reproducible() {
+        let dir = test_dir("reproducible");
+        let libraries = vec![
+            dir.join("libraries/org/quiltmc/quilt-loader.jar"),
+            dir.join("libraries/net/fabricmc/intermediary.jar"),
+        ];
+
+        let first = dir.join("first.jar");
+        create_launch_jar(&first, MAIN, &libraries, JarCompression::Deflated)
+            .await
+            .unwrap();
+
+        // Zip timestamps have a resolution of two seconds
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let second = dir.join("second.jar");
+        create_launch_jar(&second, MAIN, &libraries, JarCompression::Deflated)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read(&first).unwrap(),
+            std::fs::read(&second).unwrap()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stored_launch_jar_round_trips() {
+        let dir = test_dir("stored");
+        // Enough entries to wrap the Class-Path, with characters that need more than one byte
+        let libraries = (0..8)
+            .map(|i| dir.join(format!("libraries/org/quiltmc/ünï-{}.jar", i)))
+            .collect::<Vec<PathBuf>>();
+
+        let jar = dir.join("quilt-server-launch.jar");
+        create_launch_jar(&jar, MAIN, &libraries, JarCompression::Stored)
+            .await
+            .unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(std::fs::read(&jar).unwrap())).unwrap();
+        let mut file = archive.by_name("META-INF/MANIFEST.MF").unwrap();
+        assert_eq!(file.compression(), zip::CompressionMethod::Stored);
+
+        let mut manifest = String::new();
+        file.read_to_string(&mut manifest).unwrap();
+
+        let mut lines: Vec<String> = Vec::new();
+        for line in manifest.lines() {
+            assert!(line.len() <= MANIFEST_LINE_LENGTH, "{:?} is too long", line);
+
+            match (line.strip_prefix(' '), lines.last_mut()) {
+                (Some(continuation), Some(last)) => last.push_str(continuation),
+                _ => lines.push(line.to_string()),
+            }
+        }
+
+        assert_eq!(lines[0], "Manifest-Version: 1.0");
+        assert_eq!(lines[1], format!("Main-Class: {}", MAIN));
+
+        let class_path = lines[2].strip_prefix("Class-Path: ").unwrap();
+        let entries = class_path
+            .split(' ')
+            .map(|entry| class_path_entry_path(&dir, entry))
+            .collect::<Vec<PathBuf>>();
+        assert_eq!(entries, libraries);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn profile_keeps_key_order() {
+        let json = r#"{
+  "id": "quilt-loader-0.17.0-1.19",
+  "inheritsFrom": "1.19",
+  "type": "release",
+  "mainClass": "org.quiltmc.loader.impl.launch.knot.KnotClient",
+  "arguments": {
+    "jvm": [],
+    "game": []
+  },
+  "libraries": [
+    {
+      "url": "https://maven.quiltmc.org/repository/release/",
+      "name": "org.quiltmc:quilt-loader:0.17.0"
+    }
+  ],
+  "releaseTime": "2022-06-07T10:00:00+00:00",
+  "time": "2022-06-07T10:00:00+00:00"
+}"#;
+
+        let profile: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            crate::loaders::JsonFormat::Pretty
+                .serialize(&profile)
+                .unwrap(),
+            json
+        );
+    }
+
+    /// A server profile from before the server launcher, which starts Knot directly.
+    const PROFILE_WITHOUT_LAUNCHER: &str = r#"{
+  "id": "quilt-loader-0.16.1-1.18.2",
+  "inheritsFrom": "1.18.2",
+  "releaseTime": "2022-05-01T10:00:00+00:00",
+  "time": "2022-05-01T10:00:00+00:00",
+  "type": "release",
+  "mainClass": "org.quiltmc.loader.impl.launch.knot.KnotServer",
+  "arguments": { "game": [] },
+  "libraries": [
+    {
+      "name": "org.quiltmc:quilt-loader:0.16.1",
+      "url": "https://maven.quiltmc.org/repository/release/"
+    }
+  ]
+}"#;
+
+    /// A server profile shipping the server launcher, which sets up the class path for Knot.
+    const PROFILE_WITH_LAUNCHER: &str = r#"{
+  "id": "quilt-loader-0.17.0-1.19",
+  "inheritsFrom": "1.19",
+  "releaseTime": "2022-06-07T10:00:00+00:00",
+  "time": "2022-06-07T10:00:00+00:00",
+  "type": "release",
+  "mainClass": "org.quiltmc.loader.impl.launch.knot.KnotServer",
+  "launcherMainClass": "org.quiltmc.loader.impl.launch.server.QuiltServerLauncher",
+  "arguments": { "game": [] },
+  "libraries": [
+    {
+      "name": "org.quiltmc:quilt-loader:0.17.0",
+      "url": "https://maven.quiltmc.org/repository/release/"
+    },
+    {
+      "name": "net.fabricmc:intermediary:1.19",
+      "url": "https://maven.fabricmc.net/"
+    }
+  ]
+}"#;
+
+    #[test]
+    fn launch_jar_starts_knot_without_launcher() {
+        let profile: ServerProfile = serde_json::from_str(PROFILE_WITHOUT_LAUNCHER).unwrap();
+
+        assert_eq!(profile.launcher_main_class, None);
+        assert_eq!(
+            profile.launch_jar_main_class(),
+            "org.quiltmc.loader.impl.launch.knot.KnotServer"
+        );
+    }
+
+    #[test]
+    fn launch_jar_prefers_launcher() {
+        let profile: ServerProfile = serde_json::from_str(PROFILE_WITH_LAUNCHER).unwrap();
+
+        assert_eq!(
+            profile.launch_jar_main_class(),
+            "org.quiltmc.loader.impl.launch.server.QuiltServerLauncher"
+        );
+    }
+
+    /// Serves each path's content type and body over plain HTTP on a local port, anything else is
+    /// a 404. Returns the server's base URL.
+    async fn serve(routes: HashMap<String, (&'static str, Vec<u8>)>) -> String {
+        use tokio::{io::AsyncReadExt, net::TcpListener};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let routes = routes.clone();
+
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => request.extend_from_slice(&buf[..read]),
+                        }
+                    }
+
+                    let request = String::from_utf8_lossy(&request);
+                    let mut line = request.split(' ');
+                    let method = line.next().unwrap_or_default();
+                    let path = line.next().unwrap_or_default();
+
+                    let (status, content_type, body) = match routes.get(path) {
+                        Some((content_type, body)) => ("200 OK", *content_type, body.as_slice()),
+                        None => ("404 Not Found", "text/plain", &b"Not Found"[..]),
+                    };
+
+                    let mut response = format!(
+                        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        status,
+                        content_type,
+                        body.len()
+                    )
+                    .into_bytes();
+                    if method != "HEAD" {
+                        response.extend_from_slice(body);
+                    }
+
+                    let _ = stream.write_all(&response).await;
+                });
+            }
+        });
+
+        base
+    }
+
+    #[tokio::test]
+    async fn install_server_from_local_server() {
+        let dir = test_dir("install-server");
+        // The install records its checksums, which mustn't end up in the real data directory
+        utils::set_data_directory(dir.join("data"));
+
+        let loader = b"PK\x03\x04quilt-loader".to_vec();
+        let intermediary = b"PK\x03\x04intermediary".to_vec();
+
+        let routes = HashMap::from([
+            (
+                "/meta/versions/loader/1.19/0.17.0/server/json".to_string(),
+                (
+                    "application/json",
+                    PROFILE_WITH_LAUNCHER.as_bytes().to_vec(),
+                ),
+            ),
+            (
+                "/quilt/org/quiltmc/quilt-loader/0.17.0/quilt-loader-0.17.0.jar".to_string(),
+                ("application/java-archive", loader.clone()),
+            ),
+            (
+                "/fabric/net/fabricmc/intermediary/1.19/intermediary-1.19.jar".to_string(),
+                ("application/java-archive", intermediary.clone()),
+            ),
+        ]);
+        let base = serve(routes).await;
+
+        let urls = BaseUrls {
+            quilt_meta: format!("{}/meta", base),
+            quilt_maven: format!("{}/quilt", base),
+            quilt_snapshot_maven: format!("{}/quilt-snapshot", base),
+            fabric_maven: format!("{}/fabric", base),
+            ..BaseUrls::default()
+        };
+
+        let version = Version {
+            separator: "+build.".to_string(),
+            build: 1,
+            maven: "org.quiltmc:quilt-loader:0.17.0".to_string(),
+            version: "0.17.0".to_string(),
+            recommended: false,
+        };
+        let install = Install::builder()
+            .version(LoaderVersion::Quilt(version.clone()))
+            .side(Side::Server)
+            .dir(dir.clone())
+            .minecraft(minecraft::Version {
+                version: "1.19".to_string(),
+                stable: true,
+                kind: None,
+            })
+            .urls(urls)
+            .build()
+            .unwrap()
+            .with_version(version);
+
+        let report = install_server(install).await.unwrap();
+
+        let libraries_dir = dir.join("libraries");
+        let loader_path =
+            libraries_dir.join("org/quiltmc/quilt-loader/0.17.0/quilt-loader-0.17.0.jar");
+        let intermediary_path =
+            libraries_dir.join("net/fabricmc/intermediary/1.19/intermediary-1.19.jar");
+        assert_eq!(std::fs::read(&loader_path).unwrap(), loader);
+        assert_eq!(std::fs::read(&intermediary_path).unwrap(), intermediary);
+        assert_eq!(
+            report.libraries,
+            vec![
+                ("org.quiltmc:quilt-loader:0.17.0".to_string(), loader_path),
+                (
+                    "net.fabricmc:intermediary:1.19".to_string(),
+                    intermediary_path
+                ),
+            ]
+        );
+
+        assert_eq!(report.launch_jar, Some(dir.join("quilt-server-launch.jar")));
+        assert!(dir.join(lockfile::FILE_NAME).exists());
+        assert_eq!(verify_server(&dir).await.unwrap(), Vec::<String>::new());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn verify_client_without_jar_and_libraries() {
+        let dir = test_dir("verify-client");
+        let profile_dir = dir.join("versions/quilt-loader-0.16.1-1.18.2");
+        std::fs::create_dir_all(&profile_dir).unwrap();
+        std::fs::write(
+            profile_dir.join("quilt-loader-0.16.1-1.18.2.json"),
+            PROFILE_WITHOUT_LAUNCHER,
+        )
+        .unwrap();
+
+        // The launcher fetches the libraries and the jar is optional, neither is a problem
+        let problems = verify(Side::Client, &dir, Some(("1.18.2", "0.16.1")))
+            .await
+            .unwrap();
+        assert_eq!(problems, Vec::<String>::new());
+
+        let problems = verify(Side::Client, &dir, Some(("1.18.2", "0.16.0")))
+            .await
+            .unwrap();
+        assert_eq!(problems.len(), 1);
+
+        assert!(verify(Side::Client, &dir, None).await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,32 @@
+use crate::loaders::{fabric, forge, minecraft, quilt};
+
+/// Where each service the installer talks to lives.
+///
+/// Everything defaults to the official services, they are only passed around instead of read
+/// from the statics so an install can be pointed somewhere else, like a local test server.
+#[derive(Debug, Clone)]
+pub struct BaseUrls {
+    pub fabric_meta: String,
+    pub fabric_maven: String,
+    pub forge_files: String,
+    pub forge_maven: String,
+    pub quilt_meta: String,
+    pub quilt_maven: String,
+    pub quilt_snapshot_maven: String,
+    pub minecraft_manifest: String,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        Self {
+            fabric_meta: fabric::META.to_string(),
+            fabric_maven: fabric::MAVEN.to_string(),
+            forge_files: forge::FILES.to_string(),
+            forge_maven: forge::MAVEN.to_string(),
+            quilt_meta: quilt::META.to_string(),
+            quilt_maven: quilt::MAVEN.to_string(),
+            quilt_snapshot_maven: quilt::SNAPSHOT_MAVEN.to_string(),
+            minecraft_manifest: minecraft::MANIFEST.to_string(),
+        }
+    }
+}
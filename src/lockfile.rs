@@ -0,0 +1,179 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use tokio::{io::AsyncWriteExt, sync::Mutex};
+
+use crate::loaders::{minecraft, LoaderVersion, Side};
+
+pub const FILE_NAME: &str = "anymc.lock";
+pub const PARTIAL_FILE_NAME: &str = "anymc.lock.partial";
+
+/// Everything needed to reproduce an install exactly, written next to it as `anymc.lock`.
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Lockfile {
+    pub side: Side,
+    pub minecraft: minecraft::Version,
+    pub version: LoaderVersion,
+    pub libraries: Vec<LockedLibrary>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct LockedLibrary {
+    pub name: String,
+    pub url: String,
+    pub sha256: String,
+}
+
+impl Lockfile {
+    pub async fn read(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(FILE_NAME);
+
+        let file = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        serde_json::from_str(&file).with_context(|| format!("Invalid lockfile {}", path.display()))
+    }
+
+    pub async fn write(&self, dir: &Path) -> anyhow::Result<()> {
+        let lockfile = serde_json::to_string_pretty(self)?;
+        tokio::fs::write(dir.join(FILE_NAME), lockfile).await?;
+
+        Ok(())
+    }
+
+    pub fn library(&self, name: &str) -> Option<&LockedLibrary> {
+        self.libraries.iter().find(|lib| lib.name == name)
+    }
+}
+
+/// The libraries an unfinished install already downloaded and hashed, kept next to it as
+/// `anymc.lock.partial` so running the same install again after a crash doesn't redo them.
+///
+/// Written as JSON lines, the install it belongs to first and then a library whenever one is
+/// done, so a line cut short by the crash only loses that one library.
+#[derive(Debug, Clone)]
+pub struct Partial {
+    path: PathBuf,
+    done: Arc<HashMap<String, LockedLibrary>>,
+    file: Arc<Mutex<tokio::fs::File>>,
+}
+
+impl Partial {
+    /// Picks up where an earlier run of the same install stopped, progress of any other install
+    /// is dropped.
+    pub async fn open(
+        dir: &Path,
+        side: Side,
+        minecraft: &minecraft::Version,
+        version: &LoaderVersion,
+    ) -> anyhow::Result<Self> {
+        let path = dir.join(PARTIAL_FILE_NAME);
+        let header = serde_json::json!({
+            "side": side,
+            "minecraft": minecraft,
+            "version": version,
+        })
+        .to_string();
+
+        let mut done = HashMap::new();
+        if let Ok(existing) = tokio::fs::read_to_string(&path).await {
+            let mut lines = existing.lines();
+            if lines.next() == Some(header.as_str()) {
+                for line in lines {
+                    if let Ok(library) = serde_json::from_str::<LockedLibrary>(line) {
+                        done.insert(library.name.clone(), library);
+                    }
+                }
+            }
+        }
+        if !done.is_empty() {
+            tracing::info!(libraries = done.len(), "Resuming an unfinished install");
+        }
+
+        // Written over so a line cut short doesn't end up in front of the next one
+        let mut contents = header;
+        contents.push('\n');
+        for library in done.values() {
+            contents.push_str(&serde_json::to_string(library)?);
+            contents.push('\n');
+        }
+        tokio::fs::write(&path, contents)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        let file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await?;
+
+        Ok(Self {
+            path,
+            done: Arc::new(done),
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// A library an earlier run finished, with the hash it had then.
+    pub fn library(&self, name: &str) -> Option<&LockedLibrary> {
+        self.done.get(name)
+    }
+
+    /// Notes a library as done, only once it's downloaded and hashed.
+    pub async fn record(&self, library: &LockedLibrary) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(library)?;
+        line.push('\n');
+
+        let mut file = self.file.lock().await;
+        file.write_all(line.as_bytes()).await?;
+        // Made durable right away, it's meant to survive the machine going down too
+        file.sync_data().await?;
+
+        Ok(())
+    }
+
+    /// Removes the file once the install is done, the lockfile has everything by then.
+    pub async fn finish(self) -> anyhow::Result<()> {
+        let Self { path, file, .. } = self;
+
+        // Windows won't remove a file that's still open
+        drop(file);
+        tokio::fs::remove_file(&path).await?;
+
+        Ok(())
+    }
+}
+
+/// A downloaded library that doesn't have the hash the lockfile pinned it to.
+#[derive(Debug)]
+pub struct ChecksumMismatch {
+    pub name: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl std::fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Library {} doesn't match {}, expected sha256 {} but got {}",
+            self.name, FILE_NAME, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+pub fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -1,9 +1,22 @@
 mod loaders;
 
+mod cli;
 mod gui;
+mod lockfile;
+mod presets;
+mod settings;
+mod updates;
 mod utils;
 
+use std::path::PathBuf;
+
 use clap::Parser as _;
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    loaders::{urls::BaseUrls, JarCompression, JsonFormat, Loader, Side},
+    utils::{http::IpVersion, i18n::Language},
+};
 
 const ICON: &[u8] = include_bytes!("../assets/icon.png");
 const FABRIC_ICON: &[u8] = include_bytes!("../assets/fabric.png");
@@ -11,22 +24,293 @@ const FORGE_ICON: &[u8] = include_bytes!("../assets/forge.png");
 const QUILT_ICON: &[u8] = include_bytes!("../assets/quilt.png");
 
 fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt().with_ansi(false).init();
-
     let args = Args::parse();
 
-    if args.no_gui {
-        println!("No gui mode")
+    init_tracing(&args);
+    if let Some(dir) = &args.data_dir {
+        utils::set_data_directory(dir.clone());
+    }
+    if let Some(version) = args.ip_version {
+        utils::http::set_ip_version(version);
+    }
+    utils::crash::install_hook();
+
+    let crash_log = utils::crash::take_pending_log();
+
+    let mut headless = args.no_gui
+        || args.preset.is_some()
+        || args.verify
+        || args.check
+        || args.diff
+        || args.print_dirs
+        || args.serve.is_some()
+        || args.clean
+        || args.print_profile
+        || args.output_zip.is_some();
+
+    // Usually the GUI build started over SSH, which iced only fails on with an unhelpful error
+    if !headless && !gui::has_display() {
+        if args.dir.is_empty() {
+            anyhow::bail!(
+                "No display to open the installer window on, pass --no-gui and --dir to install without it"
+            );
+        }
+
+        eprintln!("No display to open the installer window on, installing without it");
+        headless = true;
+    }
+
+    if headless {
+        if let Some(path) = crash_log {
+            eprintln!(
+                "The installer crashed last time it was run, a crash report was saved to {}",
+                path.display()
+            );
+        }
+
+        cli::run(args)?;
     } else {
-        gui::run(args)?;
+        gui::run(args, crash_log)?;
     }
 
     Ok(())
 }
 
+/// Logs at `info` unless `-v` or `--log-level` ask for more, `RUST_LOG` still overrides both.
+fn init_tracing(args: &Args) {
+    let level = args.log_level.unwrap_or(match args.verbose {
+        0 => LogLevel::Info,
+        1 => LogLevel::Debug,
+        _ => LogLevel::Trace,
+    });
+
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level.directive()));
+
+    tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_env_filter(filter)
+        .init();
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn directive(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
 #[derive(Default, clap::Parser)]
 #[clap(about, version)]
 pub struct Args {
     #[clap(long)]
     no_gui: bool,
+
+    /// Log more, `-v` for debug and `-vv` for trace output
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Log level to use instead of `-v`, `RUST_LOG` overrides both
+    #[clap(long, value_enum, conflicts_with = "verbose")]
+    log_level: Option<LogLevel>,
+    /// Keep settings, caches and crash logs here instead of the per-user data directory
+    #[clap(long, env = "ANYMC_DATA_DIR", value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Check an existing install for missing or corrupt files without downloading anything
+    #[clap(long)]
+    verify: bool,
+
+    /// Print the profile json the selected versions would be installed from, without installing
+    #[clap(long, conflicts_with_all = ["verify", "clean", "locked"])]
+    print_profile: bool,
+
+    /// Accept JSON install requests on this localhost port, answering with progress as JSON lines
+    #[clap(long, value_name = "PORT", conflicts_with_all = ["verify", "print_profile", "check", "clean"])]
+    serve: Option<u16>,
+
+    /// Check the install directory, versions and profile download are usable, without installing
+    #[clap(long, conflicts_with_all = ["verify", "print_profile", "clean", "output_zip"])]
+    check: bool,
+
+    /// Compare a server install's `anymc.lock` with what installing the selected version would
+    /// download, the installed Minecraft version unless `--minecraft` is given
+    #[clap(long, group = "json_output", conflicts_with_all = ["verify", "print_profile", "check", "clean", "output_zip", "locked"])]
+    diff: bool,
+    /// Print where the installer reads and writes, like the Minecraft and data directories
+    #[clap(long, group = "json_output", conflicts_with_all = ["verify", "print_profile", "check", "clean", "output_zip", "serve"])]
+    print_dirs: bool,
+    /// Print `--diff` or `--print-dirs` as JSON
+    #[clap(long, requires = "json_output")]
+    json: bool,
+
+    /// List library files the selected server version no longer uses
+    #[clap(long)]
+    clean: bool,
+    /// Actually delete the files listed by `--clean`, or replace a launcher profile of the same
+    /// name instead of only updating its version and icon
+    #[clap(long)]
+    force: bool,
+
+    /// Install a preset saved in the GUI, other arguments still override what it selects
+    #[clap(long, value_name = "NAME", conflicts_with_all = ["loader", "side"])]
+    preset: Option<String>,
+
+    #[clap(long, value_enum, default_value_t)]
+    loader: Loader,
+    #[clap(long, value_enum, default_value_t)]
+    side: Side,
+    /// Install directory, repeat it to install into several servers at once
+    #[clap(long)]
+    dir: Vec<PathBuf>,
+    /// Install a server into this directory along with a `Dockerfile` and `docker-compose.yml`
+    #[clap(long, value_name = "DIR", conflicts_with_all = ["dir", "output_zip"])]
+    docker_out: Option<PathBuf>,
+    /// After a server install, check that Java can load the launch jar's main class from its
+    /// class path
+    #[clap(long)]
+    smoke_test: bool,
+    /// Java to run the Forge installer and the smoke test with and to write into start scripts,
+    /// found in `JAVA_HOME`, the `PATH` or the usual install locations by default
+    #[clap(long, value_name = "PATH")]
+    java: Option<PathBuf>,
+    /// Keep the `org.quiltmc:hashed` libraries installs normally drop, for debugging remapping
+    #[clap(long, hide = true)]
+    keep_hashed_libraries: bool,
+    /// Install a server into a temporary directory and pack it into this zip instead
+    #[clap(long, conflicts_with_all = ["dir", "locked"])]
+    output_zip: Option<PathBuf>,
+    #[clap(long)]
+    minecraft: Option<String>,
+    #[clap(long)]
+    loader_version: Option<String>,
+    /// Pick the loader version by its build number instead, Fabric and Quilt only
+    #[clap(long, conflicts_with = "loader_version")]
+    loader_build: Option<i64>,
+
+    /// Install for a Minecraft version the loader doesn't list yet, if its profile is published
+    #[clap(long, requires = "minecraft")]
+    allow_unsupported_minecraft: bool,
+
+    /// Install a client loader version older than one already installed for the same Minecraft
+    #[clap(long)]
+    allow_downgrade: bool,
+
+    /// Don't add a profile for client installs to the vanilla launcher
+    #[clap(long)]
+    no_profile: bool,
+    /// Don't write the empty version jar, only the vanilla launcher needs it
+    #[clap(long)]
+    no_placeholder_jar: bool,
+    /// Copy the vanilla client jar into the version instead of writing an empty one
+    #[clap(long, conflicts_with = "no_placeholder_jar")]
+    vanilla_jar: bool,
+    /// Keep an installed client version of the same name, installing under a `-2` style suffix
+    #[clap(long)]
+    keep_existing: bool,
+    /// Layout of the written profile json, pretty keeps the downloaded key order
+    #[clap(long, value_enum, default_value_t)]
+    json_format: JsonFormat,
+    /// Download the vanilla server jar for server installs
+    #[clap(long)]
+    download_server: bool,
+    /// Generate start scripts for server installs
+    #[clap(long)]
+    create_scripts: bool,
+    /// Compression of the server launch jar
+    #[clap(long, value_enum, default_value_t)]
+    jar_compression: JarCompression,
+    /// Agree to the Minecraft EULA (https://aka.ms/MinecraftEULA) for server installs
+    #[clap(long)]
+    accept_eula: bool,
+
+    /// Reinstall exactly what the directory's `anymc.lock` records, failing if any library changed
+    #[clap(long)]
+    locked: bool,
+
+    /// Jar to add to the server launch jar's Class-Path, such as a coremod or agent, repeatable
+    #[clap(long, value_name = "PATH")]
+    extra_lib: Vec<PathBuf>,
+
+    /// Existing server directory to take matching libraries from instead of downloading them
+    #[clap(long, value_name = "DIR")]
+    seed_from: Option<PathBuf>,
+
+    /// Limit the combined speed of library downloads, in KB/s
+    #[clap(long, value_name = "KB/s")]
+    max_bandwidth: Option<u64>,
+    /// Don't check that every server library can be downloaded before downloading any
+    #[clap(long)]
+    skip_preflight: bool,
+    /// Give up on an install that takes longer than this, removing what it wrote so far
+    #[clap(long, value_name = "SECS")]
+    timeout_total: Option<u64>,
+    /// Extra Maven repository to fall back to, as `<URL>` or `<GROUP>=<URL>` (e.g. `org.quiltmc=...`)
+    #[clap(long, value_name = "[GROUP=]URL")]
+    maven_mirror: Vec<String>,
+    /// Username for the `--maven-mirror` repositories, sent with HTTP basic auth
+    #[clap(long, requires = "maven_mirror")]
+    maven_user: Option<String>,
+    /// Password for `--maven-user`
+    #[clap(long, requires = "maven_user")]
+    maven_pass: Option<String>,
+    /// `.netrc` style file with logins for Maven repositories by host
+    #[clap(long, value_name = "PATH")]
+    maven_netrc: Option<PathBuf>,
+    /// Mojang version manifest to discover Minecraft versions from, such as a local mirror
+    #[clap(long, env = "ANYMC_MINECRAFT_MANIFEST_URL", value_name = "URL")]
+    minecraft_manifest_url: Option<String>,
+    /// Quilt meta server to fetch versions and profiles from, such as a staging one for testing
+    /// pre-release loader metadata
+    #[clap(long, env = "ANYMC_QUILT_META_URL", value_name = "URL")]
+    quilt_meta_url: Option<String>,
+    /// Fabric meta server to fetch versions from, like `--quilt-meta-url`
+    #[clap(long, env = "ANYMC_FABRIC_META_URL", value_name = "URL")]
+    fabric_meta_url: Option<String>,
+    /// Only connect over IPv4 or IPv6, for networks where the other one is broken
+    #[clap(long, value_enum)]
+    ip_version: Option<IpVersion>,
+
+    /// PNG to use as the launcher profile icon instead of the loader's
+    #[clap(long)]
+    icon: Option<PathBuf>,
+    /// JVM arguments for the launcher profile to start the game with, such as `-Xmx4G`
+    #[clap(long, value_name = "ARGS", allow_hyphen_values = true)]
+    jvm_args: Option<String>,
+
+    /// Language of the GUI, defaults to the system locale
+    #[clap(long, value_enum)]
+    lang: Option<Language>,
+}
+
+impl Args {
+    /// The official services, with whatever the arguments point elsewhere replaced.
+    fn base_urls(&self) -> BaseUrls {
+        let mut urls = BaseUrls::default();
+        if let Some(url) = &self.minecraft_manifest_url {
+            urls.minecraft_manifest = url.clone();
+        }
+        // Paths are appended to the meta URLs, so a trailing slash would double up
+        if let Some(url) = &self.quilt_meta_url {
+            urls.quilt_meta = url.trim_end_matches('/').to_string();
+        }
+        if let Some(url) = &self.fabric_meta_url {
+            urls.fabric_meta = url.trim_end_matches('/').to_string();
+        }
+
+        urls
+    }
 }
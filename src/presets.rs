@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+use crate::{
+    loaders::{Loader, Side},
+    utils,
+};
+
+/// A named selection of what to install and how, saved so it can be installed again.
+///
+/// Unlike a lockfile nothing in it is pinned, the versions are looked up again every time and
+/// left out ones fall back to the latest like they would on the command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub loader: Loader,
+    pub side: Side,
+    pub dir: Option<PathBuf>,
+    pub minecraft: Option<String>,
+    pub loader_version: Option<String>,
+
+    pub no_profile: bool,
+    pub no_placeholder_jar: bool,
+    pub keep_existing: bool,
+    pub download_server: bool,
+    pub create_scripts: bool,
+    pub compress_jar: bool,
+    pub accept_eula: bool,
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.name.fmt(f)
+    }
+}
+
+/// Every saved preset, kept in the order they were first saved.
+#[derive(Debug, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Presets(Vec<Preset>);
+
+impl Presets {
+    pub fn path() -> anyhow::Result<PathBuf> {
+        Ok(utils::get_data_directory()?.join("presets.json"))
+    }
+
+    /// Loads the saved presets, starting without any if they can't be read.
+    pub fn load() -> Self {
+        match Self::try_load() {
+            Ok(presets) => presets,
+            Err(err) => {
+                tracing::warn!(error = ?err, "Failed to load presets, starting without any");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn try_load() -> anyhow::Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = std::fs::read_to_string(path)?;
+
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.0.iter().find(|preset| preset.name == name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0.iter().map(|preset| preset.name.clone()).collect()
+    }
+
+    /// Adds a preset, replacing the one of the same name in place.
+    pub fn insert(&mut self, preset: Preset) -> anyhow::Result<()> {
+        if preset.name.trim().is_empty() {
+            anyhow::bail!("Presets need a name");
+        }
+
+        match self.0.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.0.push(preset),
+        }
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.0.retain(|preset| preset.name != name);
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> anyhow::Result<()> {
+        if to.trim().is_empty() {
+            anyhow::bail!("Presets need a name");
+        }
+        if from != to && self.get(to).is_some() {
+            anyhow::bail!("There already is a preset named {}", to);
+        }
+
+        match self.0.iter_mut().find(|preset| preset.name == from) {
+            Some(preset) => preset.name = to.to_string(),
+            None => anyhow::bail!("There is no preset named {}", from),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use crate::utils::{self, i18n::Language};
+
+/// Preferences that are remembered between runs of the GUI.
+#[derive(Debug, Default, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Show snapshots and beta loader builds, only stable versions are shown by default.
+    pub show_unstable: bool,
+    /// Look for a newer release on startup, off by default so nothing is fetched unasked.
+    pub check_for_updates: bool,
+    /// Fetch the version lists again every so often while the window stays open.
+    pub refresh_versions: bool,
+    /// Show the options most installs never need to touch.
+    pub show_advanced: bool,
+    /// Offer every loader build and the unstable toggles, simple mode only names the
+    /// recommended build for the selected Minecraft version.
+    pub expert_mode: bool,
+    /// The GUI language, follows the system locale when unset.
+    pub language: Option<Language>,
+
+    /// The last directory a client was installed into.
+    pub client_dir: Option<PathBuf>,
+    /// The last directory a server was installed into, kept apart as it is never `.minecraft`.
+    pub server_dir: Option<PathBuf>,
+    /// Directories installed into lately, most recent first.
+    pub recent_dirs: Vec<PathBuf>,
+}
+
+/// How many directories `recent_dirs` keeps.
+const MAX_RECENT_DIRS: usize = 8;
+
+impl Settings {
+    pub fn path() -> anyhow::Result<PathBuf> {
+        Ok(utils::get_data_directory()?.join("settings.json"))
+    }
+
+    /// Loads the saved settings, falling back to the defaults if they can't be read.
+    pub fn load() -> Self {
+        match Self::try_load() {
+            Ok(settings) => settings,
+            Err(err) => {
+                tracing::warn!(error = ?err, "Failed to load settings, using defaults");
+                Self::default()
+            }
+        }
+    }
+
+    fn try_load() -> anyhow::Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = std::fs::read_to_string(path)?;
+        let mut settings: Self = serde_json::from_str(&file)?;
+
+        // Offering a directory that has been deleted since would only lead to a failed install
+        settings.recent_dirs.retain(|dir| dir.is_dir());
+
+        Ok(settings)
+    }
+
+    /// Moves `dir` to the front of the recent directories, dropping the oldest past the limit.
+    pub fn add_recent_dir(&mut self, dir: &Path) {
+        self.recent_dirs.retain(|recent| recent != dir);
+        self.recent_dirs.insert(0, dir.to_path_buf());
+        self.recent_dirs.truncate(MAX_RECENT_DIRS);
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
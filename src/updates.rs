@@ -0,0 +1,37 @@
+use crate::utils;
+
+pub static RELEASES: &str = "https://api.github.com/repos/Txuritan/anymc-installer/releases/latest";
+
+/// The changelog of the running version, shown when there is no newer release.
+pub const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+
+#[derive(Debug, Clone)]
+#[derive(serde::Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub body: Option<String>,
+}
+
+impl Release {
+    /// Whether this release is newer than the running version.
+    pub fn is_newer(&self) -> bool {
+        fn parts(version: &str) -> Vec<u32> {
+            version
+                .trim_start_matches('v')
+                .split('.')
+                .map(|part| part.parse().unwrap_or(0))
+                .collect()
+        }
+
+        parts(&self.tag_name) > parts(env!("CARGO_PKG_VERSION"))
+    }
+}
+
+#[tracing::instrument(skip_all, err)]
+pub async fn fetch_latest() -> anyhow::Result<Release> {
+    Ok(utils::http::get(RELEASES)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
@@ -1,10 +1,29 @@
-use std::{collections::HashMap, path::PathBuf};
+pub mod checksums;
+pub mod cleanup;
+pub mod crash;
+pub mod http;
+pub mod i18n;
+pub mod java;
+pub mod throttle;
 
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::Context;
 use chrono::Utc;
+use png::Transformations;
+use zip::CompressionMethod;
 
-use crate::loaders::{minecraft, LoaderVersion};
+use crate::loaders::{minecraft, JsonFormat, LoaderVersion, Side};
 
 pub fn get_minecraft_directory() -> anyhow::Result<PathBuf> {
+    if cfg!(target_os = "linux") {
+        return get_linux_minecraft_directory();
+    }
+
     let mut dir = PathBuf::from(std::env::var("APPDATA")?);
 
     if cfg!(target_os = "windows") {
@@ -13,26 +32,576 @@ pub fn get_minecraft_directory() -> anyhow::Result<PathBuf> {
         dir.push("Library");
         dir.push("Application Support");
         dir.push("minecraft");
-    } else if cfg!(target_os = "linux") {
-        dir.push(".minecraft");
     }
 
     Ok(dir)
 }
 
+/// Linux launchers don't agree on where `.minecraft` lives, so use the first one that exists.
+///
+/// Falls back to `~/.minecraft` when none do yet, that's where the vanilla launcher creates it.
+fn get_linux_minecraft_directory() -> anyhow::Result<PathBuf> {
+    let home = PathBuf::from(std::env::var("HOME")?);
+    let default = home.join(".minecraft");
+
+    let mut candidates = Vec::new();
+    if let Some(data_home) = std::env::var_os("XDG_DATA_HOME") {
+        candidates.push(PathBuf::from(data_home).join("minecraft"));
+    }
+    candidates.push(default.clone());
+    candidates.push(home.join(".var/app/com.mojang.Minecraft/.minecraft"));
+
+    Ok(candidates
+        .into_iter()
+        .find(|dir| dir.exists())
+        .unwrap_or(default))
+}
+
+static DATA_DIRECTORY: OnceLock<PathBuf> = OnceLock::new();
+
+/// Moves everything the installer keeps for itself under `dir`, for running it portably.
+pub fn set_data_directory(dir: PathBuf) {
+    if DATA_DIRECTORY.set(dir).is_err() {
+        tracing::warn!("Data directory was already set");
+    }
+}
+
+/// Returns the directory the installer keeps its own files in, such as crash logs.
+///
+/// This is the one set with [`set_data_directory`] if any, otherwise a per-user OS directory.
+pub fn get_data_directory() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = DATA_DIRECTORY.get() {
+        return Ok(dir.clone());
+    }
+
+    let mut dir = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        let mut dir = PathBuf::from(std::env::var("HOME")?);
+        dir.push("Library");
+        dir.push("Application Support");
+        dir
+    } else if let Some(data_home) = std::env::var_os("XDG_DATA_HOME") {
+        PathBuf::from(data_home)
+    } else {
+        let mut dir = PathBuf::from(std::env::var("HOME")?);
+        dir.push(".local");
+        dir.push("share");
+        dir
+    };
+
+    dir.push("anymc-installer");
+
+    Ok(dir)
+}
+
+/// Expands a leading `~` and environment variables in a typed path.
+///
+/// `$VAR`, `${VAR}` and `%VAR%` all work on every platform, so paths copied from either
+/// platform's instructions do. Unknown variables are left as typed and returned by name.
+pub fn expand_path(path: &Path) -> (PathBuf, Vec<String>) {
+    let input = path.to_string_lossy();
+    let mut expanded = String::with_capacity(input.len());
+    let mut unknown = Vec::new();
+
+    let mut rest = &input[..];
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"));
+    if let (Some(after), Ok(home)) = (rest.strip_prefix('~'), home) {
+        // `~user` isn't supported, it's left as typed
+        if after.is_empty() || after.starts_with(|c: char| c == '/' || c == '\\') {
+            expanded.push_str(&home);
+            rest = after;
+        }
+    }
+
+    while let Some(start) = rest.find(|c: char| c == '$' || c == '%') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let (name, len) = match variable_at(rest) {
+            Some(variable) => variable,
+            None => {
+                expanded.push_str(&rest[..1]);
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        match std::env::var(name) {
+            Ok(value) => expanded.push_str(&value),
+            Err(_) => {
+                expanded.push_str(&rest[..len]);
+                if !unknown.iter().any(|known| known == name) {
+                    unknown.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[len..];
+    }
+    expanded.push_str(rest);
+
+    (PathBuf::from(expanded), unknown)
+}
+
+/// Reads the variable reference `text` starts with, returning its name and how long the whole
+/// reference is.
+fn variable_at(text: &str) -> Option<(&str, usize)> {
+    if let Some(braced) = text.strip_prefix("${") {
+        let end = braced.find('}')?;
+
+        return (end > 0).then(|| (&braced[..end], end + 3));
+    }
+    if let Some(name) = text.strip_prefix('$') {
+        let end = name
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(name.len());
+
+        return (end > 0).then(|| (&name[..end], end + 1));
+    }
+
+    // Windows names may have spaces and parentheses, like `%ProgramFiles(x86)%`
+    let name = text.strip_prefix('%')?;
+    let end = name.find(|c: char| c == '%' || c == '/' || c == '\\')?;
+
+    (end > 0 && name[end..].starts_with('%')).then(|| (&name[..end], end + 2))
+}
+
+/// Makes sure files can be created in a directory by writing and removing a probe file.
+pub async fn check_writable(dir: &Path) -> anyhow::Result<()> {
+    let probe = dir.join(".anymc-installer-probe");
+
+    match tokio::fs::write(&probe, b"").await {
+        Ok(()) => {
+            tokio::fs::remove_file(&probe).await?;
+
+            Ok(())
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+            anyhow::bail!("Install directory is read-only: {}", dir.display())
+        }
+        Err(err) => Err(anyhow::Error::new(err).context(format!(
+            "Install directory is not writable: {}",
+            dir.display()
+        ))),
+    }
+}
+
+/// Checks ahead of time whether an install into `dir` could work, for showing it in the GUI.
+pub async fn check_install_location(side: Side, dir: &Path) -> anyhow::Result<()> {
+    if dir.as_os_str().is_empty() {
+        anyhow::bail!("No install directory selected");
+    }
+
+    if !dir.exists() {
+        // Servers get their directory created, as long as that's possible
+        if side == Side::Server {
+            let parent = dir
+                .ancestors()
+                .skip(1)
+                .find(|parent| parent.is_dir())
+                .context("Install directory has no existing parent")?;
+
+            return check_writable(parent).await;
+        }
+
+        anyhow::bail!("Install directory doesn't exist: {}", dir.display());
+    }
+
+    if !dir.is_dir() {
+        anyhow::bail!("Install location is not a directory: {}", dir.display());
+    }
+
+    check_writable(dir).await?;
+
+    if side == Side::Client
+        && !dir.join("launcher_profiles.json").exists()
+        && !dir.join("versions").exists()
+    {
+        anyhow::bail!("Doesn't look like a Minecraft directory: {}", dir.display());
+    }
+
+    Ok(())
+}
+
+/// Room left on top of an install's downloads for the files it writes itself, like the launch jar.
+const DISK_HEADROOM: u64 = 64 * 1024 * 1024;
+
+/// Refuses an install that wouldn't fit on the volume of `dir`, rather than failing half way with
+/// an I/O error. Passes if the free space can't be determined.
+pub fn check_disk_space(dir: &Path, bytes: u64) -> anyhow::Result<()> {
+    // Servers get their directory created, so look at the nearest one that exists
+    let existing = match dir.ancestors().find(|dir| dir.is_dir()) {
+        Some(existing) => existing,
+        None => return Ok(()),
+    };
+
+    let available = match fs2::available_space(existing) {
+        Ok(available) => available,
+        Err(err) => {
+            tracing::warn!(error = ?err, "Failed to determine free disk space, skipping the check");
+            return Ok(());
+        }
+    };
+
+    let needed = bytes.saturating_add(DISK_HEADROOM);
+    if available < needed {
+        anyhow::bail!(
+            "Not enough disk space in {}, the install needs about {:.1} MB but only {:.1} MB are free",
+            existing.display(),
+            needed as f64 / (1024.0 * 1024.0),
+            available as f64 / (1024.0 * 1024.0)
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes the `eula.txt` a server needs before it starts, the same way vanilla does.
+///
+/// An existing file is left alone unless the EULA is being accepted, so an earlier agreement
+/// isn't undone by reinstalling.
+pub async fn write_eula(dir: &Path, accept: bool) -> anyhow::Result<()> {
+    let path = dir.join("eula.txt");
+    if !accept && path.exists() {
+        return Ok(());
+    }
+
+    let eula = format!(
+        "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\n#{}\neula={}\n",
+        Utc::now().to_rfc2822(),
+        accept
+    );
+    tokio::fs::write(path, eula).await?;
+
+    Ok(())
+}
+
+/// Writes `start.sh` and `start.bat` that run a server launch jar with `java`, passing
+/// `game_args` to the server before `nogui`.
+pub async fn write_start_scripts(
+    dir: &Path,
+    java: &str,
+    launch_jar: &str,
+    game_args: &[String],
+) -> anyhow::Result<()> {
+    let command = |quote: fn(&str) -> String| {
+        let mut command = format!("{} -Xmx2G -jar {}", quote(java), launch_jar);
+        for arg in game_args {
+            command.push(' ');
+            command.push_str(&quote(arg));
+        }
+        command.push_str(" nogui");
+
+        command
+    };
+
+    let sh_path = dir.join("start.sh");
+    let sh_command = command(|arg| {
+        if arg.contains(|c: char| c.is_whitespace() || "'\"$`\\".contains(c)) {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        } else {
+            arg.to_string()
+        }
+    });
+    tokio::fs::write(&sh_path, format!("#!/usr/bin/env sh\n{}\n", sh_command)).await?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        tokio::fs::set_permissions(&sh_path, std::fs::Permissions::from_mode(0o755)).await?;
+    }
+
+    let bat_path = dir.join("start.bat");
+    let bat_command = command(|arg| {
+        if arg.contains(|c: char| c.is_whitespace() || "&|<>^".contains(c)) {
+            format!("\"{}\"", arg)
+        } else {
+            arg.to_string()
+        }
+    });
+    tokio::fs::write(
+        &bat_path,
+        format!("@echo off\r\n{}\r\npause\r\n", bat_command),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Builds the `Dockerfile` next to it, mounting the install over the copied one so worlds and
+/// config changes are kept on the host.
+const DOCKER_COMPOSE: &str = r#"services:
+  server:
+    build: .
+    ports:
+      - "25565:25565"
+    volumes:
+      - .:/server
+    stdin_open: true
+    tty: true
+    restart: unless-stopped
+"#;
+
+/// Writes a `Dockerfile` that runs a server launch jar on the Eclipse Temurin JRE of the `java`
+/// release, and a `docker-compose.yml` to start it with.
+pub async fn write_docker_files(
+    dir: &Path,
+    launch_jar: &str,
+    game_args: &[String],
+    java: u32,
+) -> anyhow::Result<()> {
+    let mut command = vec!["java", "-Xmx2G", "-jar", launch_jar];
+    command.extend(game_args.iter().map(String::as_str));
+    command.push("nogui");
+
+    // Exec form, so the server gets stop signals directly instead of through a shell
+    let dockerfile = format!(
+        "FROM eclipse-temurin:{}-jre\n\
+         WORKDIR /server\n\
+         COPY . /server\n\
+         EXPOSE 25565\n\
+         CMD {}\n",
+        java,
+        serde_json::to_string(&command)?
+    );
+    tokio::fs::write(dir.join("Dockerfile"), dockerfile).await?;
+
+    tokio::fs::write(dir.join("docker-compose.yml"), DOCKER_COMPOSE).await?;
+
+    Ok(())
+}
+
+/// Packs everything in `dir` into a zip at `output`, keeping the unix permissions of the scripts.
+pub async fn zip_directory(dir: PathBuf, output: PathBuf) -> anyhow::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::create(&output)
+            .with_context(|| format!("Failed to create {}", output.display()))?;
+        let mut archive = zip::ZipWriter::new(file);
+
+        let mut pending = vec![dir.clone()];
+        while let Some(current) = pending.pop() {
+            let mut entries = std::fs::read_dir(&current)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.sort();
+
+            for path in entries {
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                let name = path
+                    .strip_prefix(&dir)?
+                    .display()
+                    .to_string()
+                    .replace('\\', "/");
+
+                #[allow(unused_mut)]
+                let mut options = zip::write::FileOptions::default()
+                    .compression_method(CompressionMethod::Deflated);
+
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt as _;
+
+                    options = options.unix_permissions(path.metadata()?.permissions().mode());
+                }
+
+                archive.start_file(name, options)?;
+                std::io::copy(&mut std::fs::File::open(&path)?, &mut archive)?;
+            }
+        }
+
+        archive.finish()?;
+
+        Ok(())
+    })
+    .await?
+}
+
+/// Compares two loader versions like `0.20.0-beta.3` the semver way, a pre-release is older than
+/// its release and build metadata after a `+` is ignored.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (&str, Option<&str>) {
+        let version = version.split('+').next().unwrap_or(version);
+
+        match version.split_once('-') {
+            Some((release, pre)) => (release, Some(pre)),
+            None => (version, None),
+        }
+    }
+
+    // Numeric parts compare as numbers so `0.10` is newer than `0.9`
+    fn compare_parts(a: &str, b: &str, separator: char) -> Ordering {
+        let mut a = a.split(separator);
+        let mut b = b.split(separator);
+
+        loop {
+            let ordering = match (a.next(), b.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => Ordering::Less,
+                (Some(_), None) => Ordering::Greater,
+                (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => a.cmp(b),
+                },
+            };
+
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+    }
+
+    let (a_release, a_pre) = split(a);
+    let (b_release, b_pre) = split(b);
+
+    compare_parts(a_release, b_release, '.').then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => compare_parts(a, b, '.'),
+    })
+}
+
+/// Largest icon side the launcher shows, anything bigger only bloats `launcher_profiles.json`.
+const MAX_ICON_SIZE: u32 = 128;
+
+/// Fully decodes a PNG to make sure it is actually usable as a launcher icon, returning it
+/// scaled down to fit [`MAX_ICON_SIZE`] if it's bigger and `None` if it can be used as is.
+pub fn fit_icon(bytes: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+
+    let (width, height) = (info.width, info.height);
+    if width <= MAX_ICON_SIZE && height <= MAX_ICON_SIZE {
+        return Ok(None);
+    }
+
+    // Expanding and stripping leaves 8 bits per channel, only the channels still differ
+    let bytes = &buffer[..info.buffer_size()];
+    let rgba: Vec<u8> = match info.color_type {
+        png::ColorType::Rgba => bytes.to_vec(),
+        png::ColorType::Rgb => bytes
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => bytes
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        png::ColorType::Grayscale => bytes
+            .iter()
+            .flat_map(|&gray| [gray, gray, gray, u8::MAX])
+            .collect(),
+        other => anyhow::bail!("Icon has an unsupported color type: {:?}", other),
+    };
+
+    let scale = width.max(height);
+    let new_width = (width * MAX_ICON_SIZE / scale).max(1);
+    let new_height = (height * MAX_ICON_SIZE / scale).max(1);
+
+    tracing::info!(
+        width,
+        height,
+        new_width,
+        new_height,
+        "Scaling down profile icon"
+    );
+
+    let scaled = scale_down(&rgba, (width, height), (new_width, new_height));
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, new_width, new_height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&scaled)?;
+    writer.finish()?;
+
+    Ok(Some(png))
+}
+
+/// Scales RGBA pixels down by averaging the block of source pixels behind every new one.
+fn scale_down(
+    rgba: &[u8],
+    (width, height): (u32, u32),
+    (to_width, to_height): (u32, u32),
+) -> Vec<u8> {
+    let mut scaled = Vec::with_capacity((to_width * to_height * 4) as usize);
+
+    for y in 0..to_height {
+        let (top, bottom) = block(y, height, to_height);
+
+        for x in 0..to_width {
+            let (left, right) = block(x, width, to_width);
+
+            let mut sum = [0u64; 4];
+            for source_y in top..bottom {
+                for source_x in left..right {
+                    let offset = ((source_y * width + source_x) * 4) as usize;
+                    for (channel, value) in sum.iter_mut().zip(&rgba[offset..offset + 4]) {
+                        *channel += u64::from(*value);
+                    }
+                }
+            }
+
+            let count = u64::from((bottom - top) * (right - left));
+            scaled.extend(sum.iter().map(|channel| (channel / count) as u8));
+        }
+    }
+
+    scaled
+}
+
+/// The range of source pixels behind pixel `index` when `from` pixels are scaled down to `to`.
+fn block(index: u32, from: u32, to: u32) -> (u32, u32) {
+    let start = index * from / to;
+    let end = ((index + 1) * from / to).max(start + 1);
+
+    (start, end)
+}
+
+/// `launcher_profiles.json`, with `serde_json`'s `preserve_order` the profiles and anything this
+/// doesn't know about are written back in the order the launcher wrote them.
 #[derive(serde::Serialize, serde::Deserialize)]
 struct LaunchProfiles {
-    profiles: HashMap<String, serde_json::Value>,
+    profiles: serde_json::Map<String, serde_json::Value>,
     settings: serde_json::Value,
     version: u32,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
 }
 
+/// Adds the launcher profile for an install, or updates the one of the same name.
+///
+/// A profile of the same name may be one the user set up themselves, so only the version, icon
+/// and `lastUsed` are updated on it and settings like `gameDir` are kept, unless `replace` is
+/// set. Returns whether an existing profile was updated.
+#[allow(clippy::too_many_arguments)]
 pub async fn generate_profile(
     dir: PathBuf,
     minecraft: minecraft::Version,
     version: LoaderVersion,
-) -> anyhow::Result<()> {
-    let profile_name = format!("{}-{}-{}", version.name(), version, minecraft);
+    suffix: Option<u32>,
+    icon: Option<&Path>,
+    java_args: Option<&str>,
+    format: JsonFormat,
+    replace: bool,
+) -> anyhow::Result<bool> {
+    let icon = version.icon_bytes(icon)?;
+    let suffix = suffix.map(|n| format!("-{}", n)).unwrap_or_default();
+    let profile_name = format!("{}-{}-{}{}", version.name(), version, minecraft, suffix);
 
     let mut profiles_json = dir.clone();
     profiles_json.push("launcher_profiles.json");
@@ -40,18 +609,170 @@ pub async fn generate_profile(
     let read_file = tokio::fs::read_to_string(&profiles_json).await?;
     let mut profiles: LaunchProfiles = serde_json::from_str(&read_file)?;
 
-    let new_profile = serde_json::json!({
-        "name": format!("{}-{}", version.name(), &minecraft),
+    // Used just now, so the launcher lists it first
+    let now = format!("{:?}", Utc::now());
+
+    let mut new_profile = serde_json::json!({
+        "name": format!("{}-{}{}", version.name(), &minecraft, suffix),
         "type": "custom",
-        "created": format!("{:?}", Utc::now()),
+        "created": now,
+        "lastUsed": now,
         "lastVersionId": profile_name.clone(),
-        "icon": format!("data:image/png;base64,{}", base64::encode(version.icon())),
+        "icon": format!("data:image/png;base64,{}", base64::encode(&icon)),
     });
+    if let Some(java_args) = java_args {
+        new_profile["javaArgs"] = java_args.into();
+    }
 
-    profiles.profiles.insert(profile_name, new_profile);
+    let merged = merge_profile(&mut profiles, profile_name, new_profile, replace);
 
-    let new_profiles = serde_json::to_string_pretty(&profiles)?;
+    let new_profiles = format.serialize(&profiles)?;
     tokio::fs::write(&profiles_json, &new_profiles).await?;
 
-    Ok(())
+    Ok(merged)
+}
+
+/// Puts `new_profile` into `profiles` under `name`, only updating the version, icon, `lastUsed`
+/// and Java arguments of an existing one unless `replace` is set. Returns whether an existing
+/// profile was updated.
+fn merge_profile(
+    profiles: &mut LaunchProfiles,
+    name: String,
+    new_profile: serde_json::Value,
+    replace: bool,
+) -> bool {
+    match profiles.profiles.get_mut(&name) {
+        Some(serde_json::Value::Object(existing)) if !replace => {
+            for key in ["lastVersionId", "icon", "lastUsed", "javaArgs"] {
+                if let Some(value) = new_profile.get(key) {
+                    existing.insert(key.to_string(), value.clone());
+                }
+            }
+
+            true
+        }
+        _ => {
+            profiles.profiles.insert(name, new_profile);
+
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles() -> LaunchProfiles {
+        serde_json::from_value(serde_json::json!({
+            "profiles": {
+                "quilt-loader-1.19": {
+                    "name": "My Quilt",
+                    "type": "custom",
+                    "lastVersionId": "quilt-loader-0.16.0-1.19",
+                    "gameDir": "/home/steve/quilt",
+                    "icon": "Grass",
+                },
+            },
+            "settings": {},
+            "version": 3,
+        }))
+        .unwrap()
+    }
+
+    fn new_profile() -> serde_json::Value {
+        serde_json::json!({
+            "name": "quilt-loader-1.19",
+            "type": "custom",
+            "lastVersionId": "quilt-loader-0.17.0-1.19",
+            "icon": "data:image/png;base64,",
+            "javaArgs": "-Xmx4G",
+        })
+    }
+
+    #[test]
+    fn merge_keeps_user_settings() {
+        let mut profiles = profiles();
+
+        let merged = merge_profile(
+            &mut profiles,
+            "quilt-loader-1.19".to_string(),
+            new_profile(),
+            false,
+        );
+        assert!(merged);
+
+        let profile = &profiles.profiles["quilt-loader-1.19"];
+        assert_eq!(profile["name"], "My Quilt");
+        assert_eq!(profile["gameDir"], "/home/steve/quilt");
+        assert_eq!(profile["lastVersionId"], "quilt-loader-0.17.0-1.19");
+        assert_eq!(profile["icon"], "data:image/png;base64,");
+        assert_eq!(profile["javaArgs"], "-Xmx4G");
+    }
+
+    #[test]
+    fn replace_overwrites_profile() {
+        let mut profiles = profiles();
+
+        let merged = merge_profile(
+            &mut profiles,
+            "quilt-loader-1.19".to_string(),
+            new_profile(),
+            true,
+        );
+        assert!(!merged);
+        assert_eq!(profiles.profiles["quilt-loader-1.19"], new_profile());
+    }
+
+    #[test]
+    fn new_profile_is_added() {
+        let mut profiles = profiles();
+
+        let merged = merge_profile(
+            &mut profiles,
+            "quilt-loader-1.18".to_string(),
+            new_profile(),
+            false,
+        );
+        assert!(!merged);
+        assert_eq!(profiles.profiles.len(), 2);
+        assert_eq!(profiles.profiles["quilt-loader-1.18"], new_profile());
+        assert_eq!(
+            profiles.profiles["quilt-loader-1.19"]["gameDir"],
+            "/home/steve/quilt"
+        );
+    }
+
+    #[test]
+    fn launcher_profiles_keep_key_order() {
+        // Keys in no particular order, as the launcher writes them
+        let json = r#"{
+  "profiles": {
+    "zzz": {
+      "type": "custom",
+      "name": "Last",
+      "lastVersionId": "1.19",
+      "created": "2022-06-07T10:00:00.000Z"
+    },
+    "aaa": {
+      "name": "First",
+      "type": "latest-release"
+    }
+  },
+  "settings": {
+    "showMenu": true,
+    "crashAssistance": false,
+    "enableAdvanced": true
+  },
+  "version": 3,
+  "selectedUser": {
+    "profile": "abc",
+    "account": "def"
+  },
+  "authenticationDatabase": {}
+}"#;
+
+        let profiles: LaunchProfiles = serde_json::from_str(json).unwrap();
+        assert_eq!(JsonFormat::Pretty.serialize(&profiles).unwrap(), json);
+    }
 }
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use crate::{lockfile, utils};
+
+const FILE_NAME: &str = "checksums.json";
+
+/// Hashes of files that were already read once, so installs and `--verify` don't hash a large
+/// `libraries` directory again every run.
+///
+/// An entry is only trusted while the file's size and modification time are unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct Checksums(Arc<Mutex<HashMap<PathBuf, Entry>>>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    size: u64,
+    /// Modification time in nanoseconds since the epoch.
+    modified: u128,
+    sha256: String,
+}
+
+impl Checksums {
+    pub fn path() -> anyhow::Result<PathBuf> {
+        Ok(utils::get_data_directory()?.join(FILE_NAME))
+    }
+
+    /// Loads the saved index, starting empty if it can't be read.
+    pub fn load() -> Self {
+        let entries = Self::path().and_then(|path| {
+            if !path.exists() {
+                return Ok(HashMap::new());
+            }
+
+            Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+        });
+
+        match entries {
+            Ok(entries) => Self(Arc::new(Mutex::new(entries))),
+            Err(err) => {
+                tracing::warn!(error = ?err, "Failed to load checksum cache, starting empty");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let index = serde_json::to_string(&*self.entries())?;
+        std::fs::write(path, index)?;
+
+        Ok(())
+    }
+
+    /// Returns the SHA-256 of a file, only reading it if it changed since it was last hashed.
+    pub async fn sha256(&self, path: &Path) -> anyhow::Result<String> {
+        let metadata = tokio::fs::metadata(path).await?;
+        let size = metadata.len();
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos())
+            .unwrap_or_default();
+
+        // Entries are keyed by absolute path as the same index is shared by every install
+        let key = tokio::fs::canonicalize(path).await?;
+
+        if let Some(entry) = self.entries().get(&key) {
+            if entry.size == size && entry.modified == modified {
+                return Ok(entry.sha256.clone());
+            }
+        }
+
+        let sha256 = lockfile::sha256(&tokio::fs::read(path).await?);
+
+        self.entries().insert(
+            key,
+            Entry {
+                size,
+                modified,
+                sha256: sha256.clone(),
+            },
+        );
+
+        Ok(sha256)
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Entry>> {
+        // A panic while holding the lock doesn't make the index itself invalid
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
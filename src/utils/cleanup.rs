@@ -0,0 +1,76 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Paths an install created and hasn't finished writing yet, removed again if it's cut short.
+///
+/// Only registered paths are ever removed, files that existed before the install are left alone.
+#[derive(Debug, Clone, Default)]
+pub struct Cleanup(Arc<Mutex<Vec<PathBuf>>>);
+
+impl Cleanup {
+    /// Registers a file or directory that is about to be created.
+    pub fn track(&self, path: &Path) {
+        self.paths().push(path.to_path_buf());
+    }
+
+    /// Marks a tracked path as complete, it is kept from now on.
+    pub fn finish(&self, path: &Path) {
+        self.paths().retain(|tracked| tracked != path);
+    }
+
+    /// Removes everything that is still unfinished, for when an install is aborted.
+    pub fn remove_all(&self) {
+        for path in self.paths().drain(..).rev() {
+            let result = if path.is_dir() {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+
+            match result {
+                Ok(()) => tracing::info!(path = ?path, "Removed incomplete file"),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    tracing::warn!(path = ?path, error = ?err, "Failed to remove incomplete file")
+                }
+            }
+        }
+    }
+
+    /// Removes everything that is unfinished once the guard is dropped, unless it was disarmed.
+    pub fn guard(&self) -> Guard {
+        Guard {
+            cleanup: self.clone(),
+            armed: true,
+        }
+    }
+
+    fn paths(&self) -> std::sync::MutexGuard<'_, Vec<PathBuf>> {
+        // A panic while holding the lock doesn't make the list itself invalid
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Cleans up after an install that is dropped before it finishes, see [`Cleanup::guard`].
+#[derive(Debug)]
+pub struct Guard {
+    cleanup: Cleanup,
+    armed: bool,
+}
+
+impl Guard {
+    /// The install ran to the end, whatever it left unfinished is up to it.
+    pub fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if self.armed {
+            self.cleanup.remove_all();
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::{backtrace::Backtrace, io::Write, path::PathBuf};
+
+use chrono::Utc;
+
+use crate::utils;
+
+/// Marker holding the path of the newest crash log, removed once the user has been told.
+const PENDING: &str = "pending-crash";
+
+/// Writes a crash log to the data directory whenever the installer panics.
+///
+/// Nothing is ever sent anywhere, the log stays on disk for the user to attach to a report.
+pub fn install_hook() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<unknown>");
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_else(|| "<unknown>".to_string());
+
+        match write_log(message, &location) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(err) => eprintln!("Failed to write crash report: {:#}", err),
+        }
+
+        default_hook(info);
+    }));
+}
+
+fn write_log(message: &str, location: &str) -> anyhow::Result<PathBuf> {
+    let dir = utils::get_data_directory()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let now = Utc::now();
+    let path = dir.join(format!("crash-{}.log", now.format("%Y%m%d-%H%M%S")));
+
+    let mut file = std::fs::File::create(&path)?;
+    writeln!(
+        file,
+        "anymc-installer {} crashed at {:?}",
+        env!("CARGO_PKG_VERSION"),
+        now
+    )?;
+    writeln!(file)?;
+    writeln!(file, "Message: {}", message)?;
+    writeln!(file, "Location: {}", location)?;
+    writeln!(file)?;
+    writeln!(file, "{}", Backtrace::force_capture())?;
+
+    std::fs::write(dir.join(PENDING), path.display().to_string())?;
+
+    Ok(path)
+}
+
+/// Returns the crash log left by a previous run, if the user hasn't been told about it yet.
+pub fn take_pending_log() -> Option<PathBuf> {
+    let marker = utils::get_data_directory().ok()?.join(PENDING);
+
+    let path = std::fs::read_to_string(&marker).ok()?;
+    if let Err(err) = std::fs::remove_file(&marker) {
+        tracing::warn!(error = ?err, "Failed to remove pending crash marker");
+    }
+
+    let path = PathBuf::from(path.trim());
+    path.exists().then_some(path)
+}
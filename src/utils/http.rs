@@ -0,0 +1,105 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    Client, IntoUrl, Response,
+};
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+static IP_VERSION: OnceLock<IpVersion> = OnceLock::new();
+
+/// IP family to connect over, for dual-stack networks where one of them is broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(clap::ValueEnum)]
+pub enum IpVersion {
+    V4,
+    V6,
+}
+
+impl IpVersion {
+    fn matches(self, addr: &SocketAddr) -> bool {
+        match self {
+            IpVersion::V4 => addr.is_ipv4(),
+            IpVersion::V6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// Only connects over `version` from then on, has to be called before the client is first used.
+pub fn set_ip_version(version: IpVersion) {
+    if IP_VERSION.set(version).is_err() {
+        tracing::warn!("IP version was already set, ignoring {:?}", version);
+    }
+}
+
+/// Resolves names like the system does, dropping the addresses of the other IP family.
+struct FamilyResolver(IpVersion);
+
+impl Resolve for FamilyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let version = self.0;
+
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| version.matches(addr))
+                .collect::<Vec<_>>();
+
+            if addrs.is_empty() {
+                let err = format!("{} has no {:?} address", name.as_str(), version);
+                return Err(err.into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The client every request goes through, built on first use so connections are pooled and kept
+/// alive for the whole run instead of being set up again for each request.
+///
+/// There is no overall request timeout as large or throttled downloads can legitimately take a
+/// while, only connecting is bounded.
+pub fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        tracing::debug!("Creating shared HTTP client");
+
+        let mut builder = Client::builder()
+            // GitHub rejects API requests without a User-Agent
+            .user_agent(concat!("anymc-installer/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(Duration::from_secs(30))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(60));
+
+        if let Some(&version) = IP_VERSION.get() {
+            builder = builder.dns_resolver(Arc::new(FamilyResolver(version)));
+        }
+
+        builder.build().expect("Failed to create HTTP client")
+    })
+}
+
+/// Shorthand for a GET request through the shared client, a pooled `reqwest::get`.
+pub async fn get(url: impl IntoUrl) -> reqwest::Result<Response> {
+    client().get(url).send().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_is_shared() {
+        assert!(std::ptr::eq(client(), client()));
+
+        // Downloads run on the runtime's worker threads, which all have to get the same one
+        let threads = [std::thread::spawn(client), std::thread::spawn(client)];
+        for thread in threads {
+            assert!(std::ptr::eq(thread.join().unwrap(), client()));
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::sync::OnceLock;
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// Languages the GUI is translated into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(clap::ValueEnum)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    /// Picks the language from the usual locale variables, English if none are set or known.
+    pub fn from_system() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|locale| !locale.is_empty())
+            .map(|locale| Self::from_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    /// Matches a locale like `de_DE.UTF-8` by its language code.
+    fn from_locale(locale: &str) -> Self {
+        match locale.split(['_', '-', '.']).next() {
+            Some("de") => Language::German,
+            _ => Language::English,
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => ENGLISH,
+            Language::German => GERMAN,
+        }
+    }
+}
+
+/// Sets the language every later lookup uses, only the first call has any effect.
+pub fn set_language(language: Language) {
+    if LANGUAGE.set(language).is_err() {
+        tracing::warn!("Language was already set, ignoring {:?}", language);
+    }
+}
+
+/// Looks up the text for `key` in the selected language.
+///
+/// Keys a translation is missing fall back to English, and unknown keys to the key itself so a
+/// typo shows up in the window instead of an empty label.
+pub fn tr(key: &'static str) -> &'static str {
+    let language = LANGUAGE.get().copied().unwrap_or_default();
+
+    lookup(language, key)
+        .or_else(|| lookup(Language::English, key))
+        .unwrap_or(key)
+}
+
+fn lookup(language: Language, key: &str) -> Option<&'static str> {
+    language
+        .table()
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, text)| *text)
+}
+
+const ENGLISH: &[(&str, &str)] = &[
+    ("accept_eula", "Accept EULA"),
+    ("advanced", "Advanced:"),
+    ("all_versions", "All versions"),
+    ("already_up_to_date", "Already up to date"),
+    ("blocked_loading", "Versions are still loading"),
+    ("blocked_location", "The install directory can't be used"),
+    ("blocked_no_loader", "Select a loader version to install"),
+    (
+        "blocked_no_minecraft",
+        "Select a Minecraft version to install",
+    ),
+    (
+        "blocked_no_versions",
+        "No versions to install, try refreshing",
+    ),
+    ("browse", "Browse..."),
+    ("check_for_updates", "Check for updates on startup"),
+    ("check_now", "Check now"),
+    ("client", "Client"),
+    ("close", "Close"),
+    (
+        "close_while_installing",
+        "An install is still running, cancel it and quit?",
+    ),
+    ("command_copied", "Command copied to the clipboard"),
+    ("compress_launch_jar", "Compress launch jar"),
+    ("copy_command", "Copy command"),
+    ("delete", "Delete"),
+    ("directory", "Directory:"),
+    (
+        "downgrade_confirm",
+        "This will downgrade the installed version, continue?",
+    ),
+    ("download_server_jar", "Download server jar"),
+    ("estimating_download", "Estimating download size"),
+    ("existing_profiles", "Installed:"),
+    ("expert_mode", "Expert mode"),
+    ("fabric_flavor", "Flavor:"),
+    ("generate_launch_script", "Generate launch script"),
+    ("generate_profile", "Generate profile"),
+    ("hide_advanced", "Fewer options"),
+    (
+        "hint_checksum",
+        "A download was corrupted, try installing again",
+    ),
+    (
+        "hint_filesystem",
+        "Check that the directory exists, is writable and has free space",
+    ),
+    (
+        "hint_network",
+        "Check your internet connection or try a different Maven mirror",
+    ),
+    ("install", "Install"),
+    ("install_cancelled", "Install cancelled"),
+    ("install_complete", "Install complete"),
+    ("install_failed", "Install failed"),
+    ("install_location", "Install Location"),
+    ("java", "Java"),
+    ("java_not_found", "Not found, install Java or set JAVA_HOME"),
+    ("java_searching", "Searching..."),
+    (
+        "java_too_old",
+        "The Java found is too old for this version, the server needs",
+    ),
+    ("keep_existing", "Keep existing version"),
+    ("loader_version", "Loader version:"),
+    ("minecraft_version", "Minecraft version:"),
+    (
+        "no_loader_for_snapshot",
+        "No loader version supports this snapshot yet",
+    ),
+    ("no_match", "No matching version"),
+    ("no_version_yet", "None available yet"),
+    ("options", "Options:"),
+    ("preset_name", "Name"),
+    ("preset_saved", "Preset saved"),
+    (
+        "preset_unavailable",
+        "Versions of the preset are no longer available",
+    ),
+    ("presets", "Presets:"),
+    ("profile_unavailable", "No longer available"),
+    ("recent", "Recent"),
+    ("refresh", "Refresh"),
+    ("releases_and_snapshots", "With snapshots"),
+    ("releases_only", "Releases"),
+    ("refresh_versions", "Refresh versions every 15 minutes"),
+    ("reinstall", "Reinstall"),
+    ("rename", "Rename"),
+    ("save", "Save"),
+    ("server", "Server"),
+    ("show_advanced", "More options"),
+    ("show_betas", "Show betas"),
+    ("show_unstable", "Show unstable versions"),
+    ("skip_placeholder_jar", "Skip placeholder jar"),
+    ("starting_install", "Starting install"),
+    ("type_version", "Type a version"),
+    ("unknown_variables", "Unknown variables, left as typed"),
+    ("update_available", "Update available"),
+    ("whats_new", "What's new"),
+];
+
+const GERMAN: &[(&str, &str)] = &[
+    ("accept_eula", "EULA akzeptieren"),
+    ("advanced", "Erweitert:"),
+    ("all_versions", "Alle Versionen"),
+    ("already_up_to_date", "Bereits aktuell"),
+    ("blocked_loading", "Versionen werden noch geladen"),
+    (
+        "blocked_location",
+        "Das Installationsverzeichnis ist nicht verwendbar",
+    ),
+    ("blocked_no_loader", "Wähle eine Loader-Version aus"),
+    ("blocked_no_minecraft", "Wähle eine Minecraft-Version aus"),
+    (
+        "blocked_no_versions",
+        "Keine Versionen verfügbar, versuche es erneut",
+    ),
+    ("browse", "Durchsuchen..."),
+    ("check_for_updates", "Beim Start nach Updates suchen"),
+    ("check_now", "Jetzt prüfen"),
+    ("client", "Client"),
+    ("close", "Schließen"),
+    (
+        "close_while_installing",
+        "Eine Installation läuft noch, abbrechen und beenden?",
+    ),
+    ("command_copied", "Befehl in die Zwischenablage kopiert"),
+    ("compress_launch_jar", "Start-Jar komprimieren"),
+    ("copy_command", "Befehl kopieren"),
+    ("delete", "Löschen"),
+    ("directory", "Verzeichnis:"),
+    (
+        "downgrade_confirm",
+        "Die installierte Version wird herabgestuft, fortfahren?",
+    ),
+    ("download_server_jar", "Server-Jar herunterladen"),
+    ("estimating_download", "Downloadgröße wird ermittelt"),
+    ("existing_profiles", "Installiert:"),
+    ("expert_mode", "Expertenmodus"),
+    ("fabric_flavor", "Variante:"),
+    ("generate_launch_script", "Startskript erstellen"),
+    ("generate_profile", "Profil erstellen"),
+    ("hide_advanced", "Weniger Optionen"),
+    (
+        "hint_checksum",
+        "Ein Download war beschädigt, versuche es erneut",
+    ),
+    (
+        "hint_filesystem",
+        "Prüfe, ob das Verzeichnis existiert, beschreibbar ist und genug Platz hat",
+    ),
+    (
+        "hint_network",
+        "Prüfe deine Internetverbindung oder versuche einen anderen Maven-Mirror",
+    ),
+    ("install", "Installieren"),
+    ("install_cancelled", "Installation abgebrochen"),
+    ("install_complete", "Installation abgeschlossen"),
+    ("install_failed", "Installation fehlgeschlagen"),
+    ("install_location", "Installationsort"),
+    ("java", "Java"),
+    (
+        "java_not_found",
+        "Nicht gefunden, installiere Java oder setze JAVA_HOME",
+    ),
+    ("java_searching", "Suche..."),
+    (
+        "java_too_old",
+        "Das gefundene Java ist zu alt für diese Version, der Server braucht",
+    ),
+    ("keep_existing", "Vorhandene Version behalten"),
+    ("loader_version", "Loader-Version:"),
+    ("minecraft_version", "Minecraft-Version:"),
+    (
+        "no_loader_for_snapshot",
+        "Noch keine Loader-Version unterstützt diesen Snapshot",
+    ),
+    ("no_match", "Keine passende Version"),
+    ("no_version_yet", "Noch keine verfügbar"),
+    ("options", "Optionen:"),
+    ("preset_name", "Name"),
+    ("preset_saved", "Vorlage gespeichert"),
+    (
+        "preset_unavailable",
+        "Die Versionen der Vorlage sind nicht mehr verfügbar",
+    ),
+    ("presets", "Vorlagen:"),
+    ("profile_unavailable", "Nicht mehr verfügbar"),
+    ("recent", "Zuletzt"),
+    ("refresh", "Aktualisieren"),
+    ("releases_and_snapshots", "Mit Snapshots"),
+    ("releases_only", "Releases"),
+    ("reinstall", "Neu installieren"),
+    ("rename", "Umbenennen"),
+    ("save", "Speichern"),
+    (
+        "refresh_versions",
+        "Versionen alle 15 Minuten aktualisieren",
+    ),
+    ("server", "Server"),
+    ("show_advanced", "Mehr Optionen"),
+    ("show_betas", "Betas anzeigen"),
+    ("show_unstable", "Instabile Versionen anzeigen"),
+    ("skip_placeholder_jar", "Platzhalter-Jar überspringen"),
+    ("starting_install", "Installation wird gestartet"),
+    ("type_version", "Version eingeben"),
+    (
+        "unknown_variables",
+        "Unbekannte Variablen, unverändert gelassen",
+    ),
+    ("update_available", "Update verfügbar"),
+    ("whats_new", "Neuigkeiten"),
+];
@@ -0,0 +1,163 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use anyhow::Context;
+
+/// A Java runtime that answered `java -version`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Java {
+    pub path: PathBuf,
+    /// The release, `8` for `1.8.0_301` and `17` for `17.0.2`.
+    pub major: u32,
+}
+
+impl Java {
+    /// Whether the runtime can run a Minecraft version needing Java `required`.
+    pub fn supports(&self, required: u32) -> bool {
+        self.major >= required
+    }
+}
+
+impl fmt::Display for Java {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Java {} ({})", self.major, self.path.display())
+    }
+}
+
+fn executable() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "java.exe"
+    } else {
+        "java"
+    }
+}
+
+/// Folders runtimes are usually installed into, each holding one folder per runtime.
+fn install_roots() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        let program_files =
+            std::env::var_os("ProgramFiles").unwrap_or_else(|| "C:\\Program Files".into());
+        let program_files = PathBuf::from(program_files);
+
+        vec![
+            program_files.join("Java"),
+            program_files.join("Eclipse Adoptium"),
+            program_files.join("Microsoft"),
+            program_files.join("Zulu"),
+        ]
+    } else if cfg!(target_os = "macos") {
+        vec![PathBuf::from("/Library/Java/JavaVirtualMachines")]
+    } else {
+        vec![PathBuf::from("/usr/lib/jvm"), PathBuf::from("/opt")]
+    }
+}
+
+/// The runtimes in the usual install locations.
+async fn installed() -> Vec<PathBuf> {
+    let mut installed = Vec::new();
+    for root in install_roots() {
+        let mut entries = match tokio::fs::read_dir(&root).await {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let home = if cfg!(target_os = "macos") {
+                entry.path().join("Contents").join("Home")
+            } else {
+                entry.path()
+            };
+
+            let java = home.join("bin").join(executable());
+            if java.is_file() {
+                installed.push(java);
+            }
+        }
+    }
+
+    installed
+}
+
+/// Finds a Java runtime, preferring `JAVA_HOME` over the `PATH` over the newest release in the
+/// usual install locations.
+pub async fn find() -> anyhow::Result<Java> {
+    let mut preferred = Vec::new();
+    if let Some(home) = std::env::var_os("JAVA_HOME") {
+        preferred.push(PathBuf::from(home).join("bin").join(executable()));
+    }
+    // Left to the OS to look up on the PATH
+    preferred.push(PathBuf::from(executable()));
+
+    for candidate in preferred {
+        if let Ok(java) = probe(&candidate).await {
+            return Ok(java);
+        }
+    }
+
+    let mut newest: Option<Java> = None;
+    for candidate in installed().await {
+        if let Ok(java) = probe(&candidate).await {
+            if newest
+                .as_ref()
+                .map_or(true, |newest| java.major > newest.major)
+            {
+                newest = Some(java);
+            }
+        }
+    }
+    if let Some(java) = newest {
+        return Ok(java);
+    }
+
+    anyhow::bail!("No Java runtime found, install Java or set JAVA_HOME")
+}
+
+/// Uses the runtime at `path` if one is given, otherwise finds one.
+pub async fn find_or(path: Option<&Path>) -> anyhow::Result<Java> {
+    match path {
+        Some(path) => probe(path).await,
+        None => find().await,
+    }
+}
+
+/// Runs `java -version` to check the runtime works and read its release.
+pub async fn probe(path: &Path) -> anyhow::Result<Java> {
+    let output = tokio::process::Command::new(path)
+        .arg("-version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("Failed to run Java at {}", path.display()))?;
+
+    if !output.status.success() {
+        anyhow::bail!("Java at {} failed to report its version", path.display());
+    }
+
+    // The version goes to stderr, as in `openjdk version "17.0.2" 2022-01-18`
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let major = parse_major(&stderr)
+        .with_context(|| format!("Unknown version reported by Java at {}", path.display()))?;
+
+    Ok(Java {
+        path: path.to_path_buf(),
+        major,
+    })
+}
+
+fn parse_major(output: &str) -> Option<u32> {
+    let version = output.lines().find_map(|line| line.split('"').nth(1))?;
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|part| part.parse::<u32>().ok());
+
+    // Up to Java 8 the release is the second number, `1.8.0_301`
+    match (parts.next()?, parts.next()) {
+        (Some(1), Some(Some(major))) => Some(major),
+        (Some(major), _) => Some(major),
+        (None, _) => None,
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A token bucket shared between concurrent downloads to cap their combined bandwidth.
+///
+/// Chunks are always let through, going over budget just makes the next caller wait longer.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    /// Bytes per second, also the most the bucket can hold.
+    rate: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Throttle {
+    pub fn new(kilobytes_per_second: u64) -> Self {
+        let rate = (kilobytes_per_second.max(1) * 1024) as f64;
+
+        Self {
+            rate,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: rate,
+                refilled: Instant::now(),
+            })),
+        }
+    }
+
+    /// Takes `bytes` out of the bucket, waiting until it has refilled enough to cover them.
+    pub async fn consume(&self, bytes: usize) {
+        let wait = {
+            let mut bucket = self.bucket.lock().expect("Throttle mutex was poisoned");
+
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+
+            bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.rate);
+            bucket.refilled = now;
+            bucket.tokens -= bytes as f64;
+
+            if bucket.tokens < 0.0 {
+                Duration::from_secs_f64(-bucket.tokens / self.rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}